@@ -0,0 +1,238 @@
+use crate::errors::{MyError, SyntaxError};
+use crate::expr::Expr;
+use crate::pattern::Pattern;
+use crate::stmt::Stmt;
+use crate::tokens::Token;
+use std::collections::{HashMap, HashSet};
+
+// static analysis over a parsed program, reporting mistakes the interpreter would only
+// notice when the code runs. top-level names are left alone, as they may be redefined.
+pub fn check(stmts: &[Stmt]) -> Vec<anyhow::Error> {
+    let mut checker = Checker::default();
+    checker.stmts(stmts);
+
+    checker.errors
+}
+
+#[derive(Default)]
+struct Checker {
+    // name -> whether its initializer has finished.
+    scopes: Vec<HashMap<String, bool>>,
+    loop_depth: usize,
+    function_depth: usize,
+    errors: Vec<anyhow::Error>,
+}
+
+impl Checker {
+    fn stmts(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::ExprStmt(expr) | Stmt::PrintStmt(expr) => self.expr(expr),
+            Stmt::VarStmt { name, initializer } => {
+                self.declare(name);
+                self.expr(initializer);
+                self.define(name);
+            }
+            Stmt::IfStmt {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.stmt(then_branch);
+                self.stmt(else_branch);
+            }
+            Stmt::WhileStmt { condition, body } => {
+                self.expr(condition);
+                self.loop_depth += 1;
+                self.stmt(body);
+                self.loop_depth -= 1;
+            }
+            Stmt::Block(stmts) => self.scoped(|checker| checker.stmts(stmts)),
+            Stmt::Break { keyword } => {
+                if self.loop_depth == 0 {
+                    self.error(keyword, "Can't use 'break' outside of a loop.");
+                }
+            }
+            Stmt::Return { keyword, value } => {
+                if self.function_depth == 0 {
+                    self.error(keyword, "Can't return from top-level code.");
+                }
+                self.expr(value);
+            }
+            Stmt::Throw { value, .. } => self.expr(value),
+            Stmt::Try {
+                body,
+                catch_clause,
+                finally_body,
+            } => {
+                self.scoped(|checker| checker.stmts(body));
+                if let Some(clause) = catch_clause {
+                    self.scoped(|checker| {
+                        checker.declare(&clause.name);
+                        checker.define(&clause.name);
+                        checker.stmts(&clause.body);
+                    });
+                }
+                if let Some(finally_body) = finally_body {
+                    self.scoped(|checker| checker.stmts(finally_body));
+                }
+            }
+            Stmt::Import { keyword, name, .. } => {
+                let name = Token {
+                    lexeme: name.clone(),
+                    ..keyword.clone()
+                };
+                self.declare(&name);
+                self.define(&name);
+            }
+            Stmt::Match { value, arms, .. } => {
+                self.expr(value);
+                for arm in arms {
+                    self.scoped(|checker| {
+                        checker.pattern(&arm.pattern);
+                        if let Some(guard) = &arm.guard {
+                            checker.expr(guard);
+                        }
+                        checker.stmt(&arm.body);
+                    });
+                }
+            }
+            Stmt::Function { name, params, body } => {
+                self.declare(name);
+                self.define(name);
+
+                // a loop around the declaration does not make `break` valid inside it.
+                let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+                self.function_depth += 1;
+                self.scoped(|checker| {
+                    for param in params {
+                        checker.declare(param);
+                        checker.define(param);
+                    }
+                    checker.stmts(body);
+                });
+                self.function_depth -= 1;
+                self.loop_depth = loop_depth;
+            }
+            Stmt::NULL => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Var(name) => {
+                let initializing = self
+                    .scopes
+                    .last()
+                    .is_some_and(|scope| scope.get(&name.lexeme) == Some(&false));
+                if initializing {
+                    self.error(name, "Can't read local variable in its own initializer.");
+                }
+            }
+            Expr::Assign { value, .. } => self.expr(value),
+            Expr::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Expr::Increment { target, .. } => self.expr(target),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Grouping(expr) => self.expr(expr),
+            Expr::List(elements) => elements.iter().for_each(|e| self.expr(e)),
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expr(callee);
+                arguments.iter().for_each(|e| self.expr(e));
+            }
+            Expr::Get { object, .. } => self.expr(object),
+            Expr::Literal(_) | Expr::Null => {}
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(name) => {
+                self.declare(name);
+                self.define(name);
+            }
+            // every alternative binds the same names.
+            Pattern::Alternative(alternatives) => {
+                let mut names = Vec::new();
+                alternatives.iter().for_each(|p| bindings(p, &mut names));
+                let mut seen = HashSet::new();
+                for name in names {
+                    if seen.insert(&name.lexeme) {
+                        self.declare(name);
+                        self.define(name);
+                    }
+                }
+            }
+            Pattern::List { elements, rest } => {
+                elements.iter().for_each(|p| self.pattern(p));
+                if let Some(rest) = rest {
+                    self.pattern(rest);
+                }
+            }
+            Pattern::Literal(_) | Pattern::Range { .. } | Pattern::Wildcard => {}
+        }
+    }
+
+    fn scoped<F: FnOnce(&mut Self)>(&mut self, f: F) {
+        self.scopes.push(HashMap::new());
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if scope.insert(name.lexeme.clone(), false).is_some() {
+            self.error(name, "Already a variable with this name in this scope.");
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn error(&mut self, token: &Token, msg: &str) {
+        let error = MyError::StaticError(SyntaxError::at(token, msg));
+        self.errors.push(error.into());
+    }
+}
+
+fn bindings<'a>(pattern: &'a Pattern, names: &mut Vec<&'a Token>) {
+    match pattern {
+        Pattern::Binding(name) => names.push(name),
+        Pattern::Alternative(patterns) => patterns.iter().for_each(|p| bindings(p, names)),
+        Pattern::List { elements, rest } => {
+            elements.iter().for_each(|p| bindings(p, names));
+            if let Some(rest) = rest {
+                bindings(rest, names);
+            }
+        }
+        Pattern::Literal(_) | Pattern::Range { .. } | Pattern::Wildcard => {}
+    }
+}
@@ -0,0 +1,122 @@
+use crate::errors::MyError;
+use crate::scanner::{is_keyword, Scanner};
+use crate::tokens::{TokenType, Type};
+use std::io::IsTerminal;
+
+// ANSI colors for the REPL: its input as it is typed and the values it echoes.
+
+const KEYWORD: &str = "\x1b[35m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[33m";
+// nil, true and false.
+const LITERAL: &str = "\x1b[36m";
+const FUNCTION: &str = "\x1b[34m";
+const ERROR: &str = "\x1b[31m";
+const COMMENT: &str = "\x1b[90m";
+const RESET: &str = "\x1b[0m";
+
+// off when stdout is not a terminal, or NO_COLOR is set to anything but "".
+pub fn enabled() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    std::io::stdout().is_terminal() && !no_color
+}
+
+// `source` with keywords, literals and comments colored, otherwise unchanged.
+pub fn highlight(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(
+            chars
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| **c == '\n')
+                .map(|(i, _)| i + 1),
+        )
+        .collect();
+    let offset = |line: usize, column: usize| line_starts[line - 1] + column - 1;
+
+    let tokens = match Scanner::new(source).scan_tokens() {
+        Ok(tokens) => tokens,
+        // color what scans; an unterminated string runs to the end.
+        Err(e) => {
+            let Some(MyError::ScanError(error)) = e.downcast_ref::<MyError>() else {
+                return source.to_string();
+            };
+            let start = offset(error.span.line, error.span.column);
+            let prefix: String = chars[..start].iter().collect();
+            let rest: String = chars[start..].iter().collect();
+            let rest = if rest.starts_with('"') {
+                paint(STRING, &rest)
+            } else {
+                rest
+            };
+            return highlight(&prefix) + &rest;
+        }
+    };
+
+    let mut out = String::new();
+    let mut at = 0;
+    for token in tokens.iter().filter(|t| t.token_type != TokenType::EOF) {
+        let start = offset(token.line, token.column);
+        out.push_str(&between(&chars[at..start]));
+        match style(token.token_type) {
+            Some(color) => out.push_str(&paint(color, &token.lexeme)),
+            None => out.push_str(&token.lexeme),
+        }
+        at = start + token.lexeme.chars().count();
+    }
+    out.push_str(&between(&chars[at..]));
+
+    out
+}
+
+// the repr of a value, colored by type.
+pub fn value(value: &Type) -> String {
+    let color = match value {
+        Type::Any(inner) => return self::value(inner),
+        Type::List(items) => {
+            let items = items.borrow().iter().map(self::value).collect::<Vec<_>>();
+            return format!("[{}]", items.join(", "));
+        }
+        Type::String(_) => STRING,
+        Type::Number(_) => NUMBER,
+        Type::Nil | Type::Bool(_) => LITERAL,
+        Type::Fun(_) | Type::Native(_) | Type::Module(_) => FUNCTION,
+        Type::Error(_) => ERROR,
+    };
+
+    paint(color, &value.repr())
+}
+
+fn style(token_type: TokenType) -> Option<&'static str> {
+    match token_type {
+        TokenType::STRING => Some(STRING),
+        TokenType::NUMBER => Some(NUMBER),
+        // keywords themselves, but colored as values.
+        TokenType::NIL | TokenType::TRUE | TokenType::FALSE => Some(LITERAL),
+        t if is_keyword(t) => Some(KEYWORD),
+        _ => None,
+    }
+}
+
+// the text between two tokens: whitespace, and comments running to the end of a line.
+fn between(chars: &[char]) -> String {
+    let text: String = chars.iter().collect();
+    text.split_inclusive('\n')
+        .map(|line| match line.find("//") {
+            Some(start) => {
+                let (code, comment) = line.split_at(start);
+                let (comment, newline) = match comment.strip_suffix('\n') {
+                    Some(comment) => (comment, "\n"),
+                    None => (comment, ""),
+                };
+                format!("{}{}{}", code, paint(COMMENT, comment), newline)
+            }
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+fn paint(color: &str, text: &str) -> String {
+    format!("{}{}{}", color, text, RESET)
+}
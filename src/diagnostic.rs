@@ -0,0 +1,91 @@
+use crate::errors::{MyError, Span};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+// how errors and warnings are written to the diagnostics stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    // the error's own message, as it has always been printed.
+    #[default]
+    Text,
+    // one JSON object per line, for editors and CI annotators.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// an error or warning with everything a tool needs to point at it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // the MyError variant name, e.g. "ParseError".
+    pub code: String,
+    pub message: String,
+    pub file: Option<PathBuf>,
+    pub span: Option<Span>,
+    // further context, outermost last, e.g. the imports that led to the error.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    // scan, parse and static errors bring their own span, other errors have none yet.
+    // the message is the bare one, the position is in the span.
+    pub fn error(error: &anyhow::Error) -> Self {
+        let (code, message, span) = match error.downcast_ref::<MyError>() {
+            Some(
+                e @ (MyError::ScanError(syntax)
+                | MyError::ParseError(syntax)
+                | MyError::StaticError(syntax)
+                | MyError::InvalidAssignmentTargetError(syntax)),
+            ) => (e.name(), e.message(), Some(syntax.span)),
+            Some(e) => (e.name(), e.message(), None),
+            None => ("Error", error.to_string(), None),
+        };
+
+        Diagnostic {
+            severity: Severity::Error,
+            code: code.to_string(),
+            message,
+            file: None,
+            span,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn warning(code: &str, message: &str, span: Option<Span>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            code: code.to_string(),
+            message: message.to_string(),
+            file: None,
+            span,
+            notes: Vec::new(),
+        }
+    }
+
+    // `{"severity", "code", "message", "file", "line", "column", "end_line", "end_column",
+    // "notes"}`, with null for anything unknown.
+    pub fn to_json(&self) -> Value {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let span = |field: fn(&Span) -> usize| self.span.as_ref().map(field);
+
+        json!({
+            "severity": severity,
+            "code": self.code,
+            "message": self.message,
+            "file": self.file.as_ref().map(|file| file.display().to_string()),
+            "line": span(|s| s.line),
+            "column": span(|s| s.column),
+            "end_line": span(|s| s.end_line),
+            "end_column": span(|s| s.end_column),
+            "notes": self.notes,
+        })
+    }
+}
@@ -0,0 +1,368 @@
+use crate::expr::Expr;
+use crate::pattern::Pattern;
+use crate::stmt::{MatchArm, Stmt};
+use crate::tokens::{format_number, Token, TokenType, Type};
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+
+// debugging views of the scanner and parser output, used by `myjlox tokens` and `myjlox ast`.
+
+// one token per line: `line:column TYPE lexeme`, plus the literal for strings and numbers.
+pub fn tokens_text(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        out.push_str(&format!(
+            "{}:{} {:?} {}",
+            token.line, token.column, token.token_type, token.lexeme
+        ));
+        if matches!(token.token_type, TokenType::STRING | TokenType::NUMBER) {
+            out.push_str(&format!(" {}", inline_literal(&literal(&token.literal))));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn tokens_json(tokens: &[Token]) -> Value {
+    let tokens = tokens
+        .iter()
+        .map(|token| {
+            json!({
+                "type": format!("{:?}", token.token_type),
+                "lexeme": token.lexeme,
+                "literal": literal(&token.literal),
+                "line": token.line,
+                "column": token.column,
+            })
+        })
+        .collect();
+
+    Value::Array(tokens)
+}
+
+// the syntax tree as nested objects, each with a "type" and its fields in source order.
+pub fn ast_json(stmts: &[Stmt]) -> Value {
+    Value::Array(stmts.iter().map(stmt).collect())
+}
+
+// s-expressions, broken over lines when a node does not fit in one.
+pub fn ast_text(stmts: &[Stmt]) -> String {
+    let mut out = String::new();
+    for node in stmts.iter().map(stmt) {
+        out.push_str(&render(&node, 0));
+        out.push('\n');
+    }
+
+    out
+}
+
+fn stmt(stmt: &Stmt) -> Value {
+    match stmt {
+        Stmt::ExprStmt(e) => json!({"type": "Expression", "expression": expr(e)}),
+        Stmt::PrintStmt(e) => json!({"type": "Print", "expression": expr(e)}),
+        Stmt::VarStmt { name, initializer } => json!({
+            "type": "Var",
+            "name": name.lexeme,
+            "initializer": expr(initializer),
+            "line": name.line,
+        }),
+        Stmt::IfStmt {
+            condition,
+            then_branch,
+            else_branch,
+        } => json!({
+            "type": "If",
+            "condition": expr(condition),
+            "then": self::stmt(then_branch),
+            "else": self::stmt(else_branch),
+        }),
+        Stmt::WhileStmt { condition, body } => json!({
+            "type": "While",
+            "condition": expr(condition),
+            "body": self::stmt(body),
+        }),
+        Stmt::Block(stmts) => json!({"type": "Block", "statements": ast_json(stmts)}),
+        Stmt::Break { keyword } => json!({"type": "Break", "line": keyword.line}),
+        Stmt::Return { keyword, value } => json!({
+            "type": "Return",
+            "value": expr(value),
+            "line": keyword.line,
+        }),
+        Stmt::Throw { keyword, value } => json!({
+            "type": "Throw",
+            "value": expr(value),
+            "line": keyword.line,
+        }),
+        Stmt::Try {
+            body,
+            catch_clause,
+            finally_body,
+        } => json!({
+            "type": "Try",
+            "body": ast_json(body),
+            "catch": catch_clause.as_ref().map(|clause| json!({
+                "type": "Catch",
+                "name": clause.name.lexeme,
+                "body": ast_json(&clause.body),
+            })),
+            "finally": finally_body.as_deref().map(ast_json),
+        }),
+        Stmt::Import {
+            keyword,
+            path,
+            name,
+        } => json!({
+            "type": "Import",
+            "name": name,
+            "path": path,
+            "line": keyword.line,
+        }),
+        Stmt::Match {
+            keyword,
+            value,
+            arms,
+        } => json!({
+            "type": "Match",
+            "value": expr(value),
+            "arms": arms.iter().map(arm).collect::<Vec<_>>(),
+            "line": keyword.line,
+        }),
+        Stmt::Function { name, params, body } => json!({
+            "type": "Function",
+            "name": name.lexeme,
+            "params": params.iter().map(|p| p.lexeme.clone()).collect::<Vec<_>>(),
+            "body": ast_json(body),
+            "line": name.line,
+        }),
+        Stmt::NULL => Value::Null,
+    }
+}
+
+fn arm(arm: &MatchArm) -> Value {
+    json!({
+        "type": "Arm",
+        "pattern": pattern(&arm.pattern),
+        "guard": arm.guard.as_ref().map(expr),
+        "body": stmt(&arm.body),
+    })
+}
+
+fn pattern(pattern: &Pattern) -> Value {
+    match pattern {
+        Pattern::Literal(value) => json!({"type": "Literal", "value": literal(value)}),
+        Pattern::Range {
+            start,
+            end,
+            inclusive,
+        } => json!({
+            "type": "Range",
+            "start": literal(&Type::Number(*start)),
+            "end": literal(&Type::Number(*end)),
+            "inclusive": inclusive,
+        }),
+        Pattern::Wildcard => json!({"type": "Wildcard"}),
+        Pattern::Binding(name) => json!({"type": "Binding", "name": name.lexeme}),
+        Pattern::Alternative(alternatives) => json!({
+            "type": "Alternative",
+            "alternatives": alternatives.iter().map(self::pattern).collect::<Vec<_>>(),
+        }),
+        Pattern::List { elements, rest } => json!({
+            "type": "ListPattern",
+            "elements": elements.iter().map(self::pattern).collect::<Vec<_>>(),
+            "rest": rest.as_deref().map(self::pattern),
+        }),
+    }
+}
+
+fn expr(expr: &Expr) -> Value {
+    match expr {
+        Expr::Literal(value) => json!({"type": "Literal", "value": literal(value)}),
+        Expr::Var(name) => json!({"type": "Variable", "name": name.lexeme, "line": name.line}),
+        Expr::Assign { name, value } => json!({
+            "type": "Assign",
+            "name": name.lexeme,
+            "value": self::expr(value),
+            "line": name.line,
+        }),
+        Expr::CompoundAssign { target, op, value } => json!({
+            "type": "CompoundAssign",
+            "op": op.lexeme,
+            "target": self::expr(target),
+            "value": self::expr(value),
+            "line": op.line,
+        }),
+        Expr::Increment { target, op, prefix } => json!({
+            "type": "Increment",
+            "op": op.lexeme,
+            "prefix": prefix,
+            "target": self::expr(target),
+            "line": op.line,
+        }),
+        Expr::Binary { left, op, right } => json!({
+            "type": "Binary",
+            "op": op.lexeme,
+            "left": self::expr(left),
+            "right": self::expr(right),
+            "line": op.line,
+        }),
+        Expr::Logical { left, op, right } => json!({
+            "type": "Logical",
+            "op": op.lexeme,
+            "left": self::expr(left),
+            "right": self::expr(right),
+            "line": op.line,
+        }),
+        Expr::Unary { op, right } => json!({
+            "type": "Unary",
+            "op": op.lexeme,
+            "right": self::expr(right),
+            "line": op.line,
+        }),
+        Expr::Grouping(inner) => json!({"type": "Grouping", "expression": self::expr(inner)}),
+        Expr::List(elements) => json!({
+            "type": "List",
+            "elements": elements.iter().map(self::expr).collect::<Vec<_>>(),
+        }),
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => json!({
+            "type": "Conditional",
+            "condition": self::expr(condition),
+            "then": self::expr(then_branch),
+            "else": self::expr(else_branch),
+        }),
+        Expr::Call {
+            callee,
+            paren,
+            arguments,
+        } => json!({
+            "type": "Call",
+            "callee": self::expr(callee),
+            "arguments": arguments.iter().map(self::expr).collect::<Vec<_>>(),
+            "line": paren.line,
+        }),
+        Expr::Get { object, name } => json!({
+            "type": "Get",
+            "object": self::expr(object),
+            "name": name.lexeme,
+            "line": name.line,
+        }),
+        Expr::Null => Value::Null,
+    }
+}
+
+fn literal(value: &Type) -> Value {
+    match value {
+        Type::Nil => Value::Null,
+        Type::Bool(b) => Value::Bool(*b),
+        Type::Number(n) => serde_json::Number::from_f64(*n).map_or(Value::Null, Value::Number),
+        Type::String(s) => Value::String(s.clone()),
+        other => Value::String(other.to_string()),
+    }
+}
+
+const WIDTH: usize = 80;
+
+fn render(value: &Value, indent: usize) -> String {
+    let parts: Vec<Cow<Value>> = match value {
+        Value::Object(fields) => match fields.get("type").and_then(Value::as_str) {
+            Some("Literal") | Some("Variable") => return inline(value),
+            _ => node_parts(fields),
+        },
+        Value::Array(items) if !items.is_empty() => items.iter().map(Cow::Borrowed).collect(),
+        _ => return inline(value),
+    };
+
+    let flat = inline(value);
+    if indent + flat.len() <= WIDTH && !flat.contains('\n') {
+        return flat;
+    }
+
+    // node children are indented under the head, list items line up after the bracket.
+    let (open, close, head, child_indent) = match value {
+        Value::Object(fields) => ("(", ")", node_head(fields), indent + 2),
+        _ => ("[", "]", String::new(), indent + 1),
+    };
+    let pad = " ".repeat(child_indent);
+    let mut out = format!("{open}{head}");
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 || !head.is_empty() {
+            out.push('\n');
+            out.push_str(&pad);
+        }
+        out.push_str(&render(part, child_indent));
+    }
+    out.push_str(close);
+
+    out
+}
+
+fn inline(value: &Value) -> String {
+    match value {
+        Value::Object(fields) => match fields.get("type").and_then(Value::as_str) {
+            Some("Literal") => fields.get("value").map(inline_literal).unwrap_or_default(),
+            Some("Variable") => fields
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            _ => {
+                let parts = node_parts(fields).into_iter().map(|part| inline(&part));
+                let parts = std::iter::once(node_head(fields)).chain(parts);
+                format!("({})", parts.collect::<Vec<_>>().join(" "))
+            }
+        },
+        Value::Array(items) => {
+            let items = items.iter().map(inline).collect::<Vec<_>>();
+            format!("[{}]", items.join(" "))
+        }
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.as_f64().map(format_number).unwrap_or_default(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => "nil".to_string(),
+    }
+}
+
+// quoted, so `"1"` and `1` read differently.
+fn inline_literal(value: &Value) -> String {
+    match value {
+        Value::String(_) => value.to_string(),
+        _ => inline(value),
+    }
+}
+
+// operators lead their node, `(+ 1 2)`; anything else leads with its type.
+fn node_head(fields: &Map<String, Value>) -> String {
+    let kind = fields
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    match (kind, fields.get("op").and_then(Value::as_str)) {
+        ("Binary" | "Logical" | "Unary", Some(op)) => op.to_string(),
+        _ => kind.to_lowercase(),
+    }
+}
+
+fn node_parts(fields: &Map<String, Value>) -> Vec<Cow<'_, Value>> {
+    let operator = matches!(
+        fields.get("type").and_then(Value::as_str),
+        Some("Binary" | "Logical" | "Unary")
+    );
+    fields
+        .iter()
+        .filter(|(key, value)| match key.as_str() {
+            "type" | "line" => false,
+            "op" => !operator,
+            // `prefix` only says where the operator goes.
+            "inclusive" | "prefix" => value.as_bool() == Some(true),
+            _ => !value.is_null(),
+        })
+        .map(|(key, value)| match key.as_str() {
+            "inclusive" | "prefix" => Cow::Owned(Value::String(key.clone())),
+            _ => Cow::Borrowed(value),
+        })
+        .collect()
+}
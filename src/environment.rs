@@ -3,14 +3,14 @@ use crate::tokens::Token;
 use crate::tokens::Type;
 use anyhow::Result;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
 
-//TODO
+// `None` marks a variable declared without initializer, which is distinct from `nil`.
 #[derive(Debug, PartialEq)]
 pub struct Environment {
     enclosing: Option<Rc<RefCell<Environment>>>,
-    values: RefCell<HashMap<String, Type>>,
+    values: RefCell<HashMap<String, Option<Type>>>,
 }
 
 impl Environment {
@@ -24,18 +24,19 @@ impl Environment {
     pub fn define(&self, name: &str, value: &Type) {
         self.values
             .borrow_mut()
-            .insert(name.to_string(), value.clone());
+            .insert(name.to_string(), Some(value.clone()));
     }
 
-    pub fn set_env(&mut self, environment: Rc<RefCell<Environment>>) {
-        self.enclosing = Some(environment.clone());
+    // `var a;` declares `a` but reading it before assignment is an error.
+    pub fn declare(&self, name: &str) {
+        self.values.borrow_mut().insert(name.to_string(), None);
     }
 
     pub fn get(&self, name: &Token) -> Result<Type> {
         match self.values.borrow().get(&name.lexeme) {
             Some(v) => match v {
-                Type::Nil => Err(MyError::EnValueNotInitError(name.lexeme.clone()).into()),
-                _ => Ok(v.clone()),
+                None => Err(MyError::EnValueNotInitError(name.lexeme.clone()).into()),
+                Some(v) => Ok(v.clone()),
             },
             None => {
                 // enclosing get.
@@ -48,10 +49,42 @@ impl Environment {
         }
     }
 
+    // lookup in this scope only, without walking the enclosing chain.
+    pub fn get_value(&self, name: &str) -> Option<Type> {
+        self.values.borrow().get(name).cloned().flatten()
+    }
+
+    // names bound here and in every enclosing scope.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.values.borrow().keys().cloned().collect();
+        if let Some(ref enclosing) = self.enclosing {
+            names.extend(enclosing.borrow().names());
+        }
+
+        names
+    }
+
+    // bindings visible from this scope, inner ones shadowing outer, sorted by name.
+    // `None` is a variable that was declared but not assigned.
+    pub fn bindings(&self) -> Vec<(String, Option<Type>)> {
+        let mut bindings: BTreeMap<String, Option<Type>> = match self.enclosing {
+            Some(ref enclosing) => enclosing.borrow().bindings().into_iter().collect(),
+            None => BTreeMap::new(),
+        };
+        bindings.extend(
+            self.values
+                .borrow()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+
+        bindings.into_iter().collect()
+    }
+
     pub fn assign(&self, name: &Token, value: &Type) -> Result<()> {
         match self.values.borrow_mut().get_mut(&name.lexeme) {
             Some(v) => {
-                *v = value.clone();
+                *v = Some(value.clone());
 
                 Ok(())
             }
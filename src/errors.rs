@@ -1,31 +1,224 @@
+use crate::tokens::{Token, TokenType};
+
 #[derive(Debug)]
 pub enum MyError {
-    ParseError(String),
+    ScanError(SyntaxError),
+    ParseError(SyntaxError),
+    // found by the checker without running the program.
+    StaticError(SyntaxError),
     CastError(String),
     DividedbyzeroError,
     NotImplementedError,
     EnValueNotFoundError(String),
     EnValueNotInitError(String),
-    InvalidAssignmentTargetError(String),
+    InvalidAssignmentTargetError(SyntaxError),
     BreakNotInLoop,
+    ReturnNotInFunction,
     NotCallableError,
-    MaxArgumentNumError,
+    ArityMismatchError(usize, usize),
+    UndefinedPropertyError(String),
+    IndexError(String),
+    // script file access outside what the host allowed.
+    PermissionError(String),
+    IoError(String),
+    ModuleNotFoundError(String),
+    ImportCycleError(String),
+    // raised by exit(code); try/catch never intercepts it.
+    Exit(i32),
+    // a value thrown by `throw`, looked up by id in the interpreter.
+    ThrownError(usize, String),
+}
+
+impl MyError {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MyError::ScanError(_) => "ScanError",
+            MyError::ParseError(_) => "ParseError",
+            MyError::StaticError(_) => "StaticError",
+            MyError::CastError(_) => "CastError",
+            MyError::DividedbyzeroError => "DividedbyzeroError",
+            MyError::NotImplementedError => "NotImplementedError",
+            MyError::EnValueNotFoundError(_) => "EnValueNotFoundError",
+            MyError::EnValueNotInitError(_) => "EnValueNotInitError",
+            MyError::InvalidAssignmentTargetError(_) => "InvalidAssignmentTargetError",
+            MyError::BreakNotInLoop => "BreakNotInLoop",
+            MyError::ReturnNotInFunction => "ReturnNotInFunction",
+            MyError::NotCallableError => "NotCallableError",
+            MyError::ArityMismatchError(_, _) => "ArityMismatchError",
+            MyError::UndefinedPropertyError(_) => "UndefinedPropertyError",
+            MyError::IndexError(_) => "IndexError",
+            MyError::PermissionError(_) => "PermissionError",
+            MyError::IoError(_) => "IoError",
+            MyError::ModuleNotFoundError(_) => "ModuleNotFoundError",
+            MyError::ImportCycleError(_) => "ImportCycleError",
+            MyError::Exit(_) => "Exit",
+            MyError::ThrownError(_, _) => "ThrownError",
+        }
+    }
+
+    // what went wrong without the "... error occurred" wrapping or a line number, for
+    // diagnostics that carry the kind and position separately.
+    pub fn message(&self) -> String {
+        match self {
+            MyError::ScanError(err)
+            | MyError::ParseError(err)
+            | MyError::StaticError(err)
+            | MyError::InvalidAssignmentTargetError(err) => err.message.clone(),
+            MyError::CastError(err)
+            | MyError::IndexError(err)
+            | MyError::PermissionError(err)
+            | MyError::IoError(err) => without_line(err).to_string(),
+            MyError::ThrownError(_, err) => err.clone(),
+            MyError::DividedbyzeroError => "Divided by zero.".to_string(),
+            MyError::NotImplementedError => "Not implemented.".to_string(),
+            MyError::NotCallableError => "Not callable.".to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+// "[Line 3]: Operand must be a number" without its "[Line 3]: ".
+fn without_line(message: &str) -> &str {
+    message
+        .strip_prefix("[Line ")
+        .and_then(|rest| rest.split_once("]: "))
+        .map_or(message, |(_, message)| message)
+}
+
+// process exit statuses, after sysexits.h as in jlox.
+pub const EXIT_DATA_ERR: i32 = 65;
+pub const EXIT_SOFTWARE: i32 = 70;
+pub const EXIT_IO_ERR: i32 = 74;
+
+// 65 for scan, parse and static errors, 70 for runtime errors, 74 for I/O errors,
+// and the script's own code for exit(code).
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    match error.downcast_ref::<MyError>() {
+        Some(MyError::Exit(code)) => *code,
+        Some(
+            MyError::ScanError(_)
+            | MyError::ParseError(_)
+            | MyError::StaticError(_)
+            | MyError::InvalidAssignmentTargetError(_)
+            | MyError::BreakNotInLoop
+            | MyError::ReturnNotInFunction,
+        ) => EXIT_DATA_ERR,
+        Some(MyError::IoError(_)) => EXIT_IO_ERR,
+        Some(_) => EXIT_SOFTWARE,
+        None if error.downcast_ref::<std::io::Error>().is_some() => EXIT_IO_ERR,
+        None => EXIT_SOFTWARE,
+    }
+}
+
+// a stretch of source, 1-based; the end column is one past the last char.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    pub fn of(token: &Token) -> Self {
+        let (mut end_line, mut end_column) = (token.line, token.column);
+        for c in token.lexeme.chars() {
+            if c == '\n' {
+                end_line += 1;
+                end_column = 1;
+            } else {
+                end_column += 1;
+            }
+        }
+
+        Span {
+            line: token.line,
+            column: token.column,
+            end_line,
+            end_column,
+        }
+    }
+}
+
+// a mistake found before the program runs, with where it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub message: String,
+    // " at 'x'", " at end" or nothing.
+    pub location: String,
+    pub span: Span,
+}
+
+impl SyntaxError {
+    pub fn at(token: &Token, message: &str) -> Self {
+        let location = match token.token_type {
+            TokenType::EOF => " at end".to_string(),
+            _ => format!(" at '{}'", token.lexeme),
+        };
+
+        SyntaxError {
+            message: message.to_string(),
+            location,
+            span: Span::of(token),
+        }
+    }
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[line {}] Error{}: {}",
+            self.span.line, self.location, self.message
+        )
+    }
+}
+
+// a runtime error as seen by a script's catch block.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ErrorValue {
+    pub kind: String,
+    pub message: String,
+    pub line: usize,
 }
 
 impl std::fmt::Display for MyError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
-            MyError::ParseError(ref err) => write!(f, "Parsing error occurred {:?}", err),
+            MyError::ScanError(ref err) => {
+                write!(f, "Scanning error occurred {:?}", err.to_string())
+            }
+            MyError::ParseError(ref err) => {
+                write!(f, "Parsing error occurred {:?}", err.to_string())
+            }
+            MyError::StaticError(ref err) => {
+                write!(f, "Static error occurred {:?}", err.to_string())
+            }
             MyError::CastError(ref err) => write!(f, "Casting error occurred {:?}", err),
             MyError::DividedbyzeroError => write!(f, "Divided by zero Error occurred"),
             MyError::NotImplementedError => write!(f, "Not implemented Error occurred"),
             MyError::BreakNotInLoop => write!(f, "Break must in loop."),
+            MyError::ReturnNotInFunction => write!(f, "Return must in function."),
             MyError::NotCallableError => write!(f, "Not callable Error occurred."),
-            MyError::MaxArgumentNumError => write!(f, "Argument number excedding the limit"),
+            MyError::ArityMismatchError(expected, got) => {
+                write!(f, "Expected {} arguments but got {}.", expected, got)
+            }
+            MyError::UndefinedPropertyError(ref err) => write!(f, "Undefined property {}.", err),
+            MyError::IndexError(ref err) => write!(f, "Index error occurred {:?}", err),
+            MyError::PermissionError(ref err) => write!(f, "Permission denied: {}", err),
+            MyError::IoError(ref err) => write!(f, "IO error occurred {:?}", err),
+            MyError::ModuleNotFoundError(ref err) => write!(f, "Module {:?} not found.", err),
+            MyError::ImportCycleError(ref err) => write!(f, "Import cycle detected: {}", err),
+            MyError::Exit(code) => write!(f, "Exit with code {}", code),
+            MyError::ThrownError(_, ref err) => write!(f, "Uncaught exception: {}", err),
             MyError::EnValueNotFoundError(ref err) => write!(f, "Undefined variable {}.", err),
             MyError::EnValueNotInitError(ref err) => write!(f, "Uninitialized variable {}.", err),
             MyError::InvalidAssignmentTargetError(ref err) => {
-                write!(f, "Invalid assignment target Error occurred. {:?}", err)
+                write!(
+                    f,
+                    "Invalid assignment target Error occurred. {:?}",
+                    err.to_string()
+                )
             }
         }
     }
@@ -6,6 +6,18 @@ pub enum Expr {
         name: Token,
         value: Box<Expr>,
     },
+    // `a += 1`; the target is evaluated once.
+    CompoundAssign {
+        target: Box<Expr>,
+        op: Token,
+        value: Box<Expr>,
+    },
+    // `++a` / `a--`.
+    Increment {
+        target: Box<Expr>,
+        op: Token,
+        prefix: bool,
+    },
     Binary {
         left: Box<Expr>,
         op: Token,
@@ -17,20 +29,47 @@ pub enum Expr {
         right: Box<Expr>,
     },
     Grouping(Box<Expr>),
+    List(Vec<Expr>),
     Var(Token),
     Logical {
         left: Box<Expr>,
         op: Token,
         right: Box<Expr>,
     },
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
     Call {
         callee: Box<Expr>,
         paren: Token,
         arguments: Vec<Expr>,
     },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
     Null,
 }
 
+impl Expr {
+    // the token an expression is anchored to, for runtime error locations.
+    pub fn token(&self) -> Option<&Token> {
+        use Expr::*;
+        match self {
+            Assign { name, .. } | Var(name) | Get { name, .. } => Some(name),
+            Binary { op, .. }
+            | Unary { op, .. }
+            | Logical { op, .. }
+            | CompoundAssign { op, .. }
+            | Increment { op, .. } => Some(op),
+            Call { paren, .. } => Some(paren),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use Expr::*;
@@ -43,18 +82,37 @@ impl std::fmt::Display for Expr {
                     format!("{op_str}{right}")
                 }
                 Grouping(expr) => expr_helper(expr),
+                List(elements) => {
+                    let items = elements.iter().map(expr_helper).collect::<Vec<_>>();
+                    format!("[{}]", items.join(", "))
+                }
                 Binary { left, op, right } => {
                     let left = expr_helper(left);
                     let op_str = op.lexeme.clone();
                     let right = expr_helper(right);
                     format!("{left}{op_str}{right}")
                 }
-                Var(token) => format!("{}", token.lexeme.clone()),
-                Null => format!(""),
+                Var(token) => token.lexeme.clone(),
+                Null => String::new(),
                 Assign { name, value } => format!("{} = {}", name.lexeme, value),
+                CompoundAssign { target, op, value } => {
+                    format!("{} {} {}", target, op.lexeme, value)
+                }
+                Increment { target, op, prefix } => {
+                    if *prefix {
+                        format!("{}{}", op.lexeme, target)
+                    } else {
+                        format!("{}{}", target, op.lexeme)
+                    }
+                }
                 Logical { left, op, right } => {
                     format!("{} {} {}", left, op.lexeme.clone(), right)
                 }
+                Conditional {
+                    condition,
+                    then_branch,
+                    else_branch,
+                } => format!("{} ? {} : {}", condition, then_branch, else_branch),
                 Call {
                     callee,
                     paren: _,
@@ -62,6 +120,7 @@ impl std::fmt::Display for Expr {
                 } => {
                     format!("{} ({:?})", callee, arguments)
                 }
+                Get { object, name } => format!("{}.{}", object, name.lexeme),
             }
         }
 
@@ -1,50 +1,365 @@
+use crate::diagnostic::{Diagnostic, ErrorFormat};
 use crate::environment::Environment;
-use crate::errors::MyError;
+use crate::errors::{ErrorValue, MyError, Span, SyntaxError};
 use crate::expr::Expr;
-use crate::stmt::{Callable, LoxFunction, Stmt};
-use crate::tokens::{TokenType, Type};
+use crate::module::{self, Module};
+use crate::natives;
+use crate::parser::Parser;
+use crate::pattern::Pattern;
+use crate::scanner::Scanner;
+use crate::stmt::{Callable, LoxFunction, NativeFunction, Stmt};
+use crate::tokens::{Token, TokenType, Type};
 use anyhow::Result;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+// how a statement finished.
+#[derive(Debug, PartialEq)]
+pub enum Flow {
+    Normal,
+    Break,
+    Return(Type),
+}
+
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
-    environment: Rc<RefCell<Environment>>,
-    is_REPL: RefCell<bool>,
-    is_looping: RefCell<bool>,
+    environment: RefCell<Rc<RefCell<Environment>>>,
+    builtins: Rc<RefCell<Environment>>,
+    is_repl: RefCell<bool>,
+    // how the REPL shows the value of an expression statement.
+    echo: RefCell<fn(&Type) -> String>,
+    // the token evaluated last, where a runtime error is reported.
+    position: RefCell<Span>,
+    // where the error on its way out was raised, recorded before it left its file.
+    failure: RefCell<Option<Diagnostic>>,
+    error_format: RefCell<ErrorFormat>,
+    // values in flight from `throw`, keyed by the id in MyError::ThrownError.
+    thrown: RefCell<HashMap<usize, Type>>,
+    next_thrown: RefCell<usize>,
+    // file being executed, imports resolve relative to it.
+    current_file: RefCell<Option<PathBuf>>,
+    search_paths: RefCell<Vec<PathBuf>>,
+    modules: RefCell<HashMap<PathBuf, Rc<Module>>>,
+    // files currently executing, for cycle detection.
+    loading: RefCell<Vec<PathBuf>>,
+    // None reads the process stdin, which the REPL shares.
+    input: RefCell<Option<Box<dyn BufRead>>>,
+    out: RefCell<Box<dyn Write>>,
+    err: RefCell<Box<dyn Write>>,
 }
 
 impl Interpreter {
-    pub fn new(is_REPL: bool) -> Self {
+    pub fn new(is_repl: bool) -> Self {
+        Self::with_output(
+            is_repl,
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
+        )
+    }
+
+    // `out` receives program output, `err` receives diagnostics.
+    pub fn with_output(is_repl: bool, out: Box<dyn Write>, err: Box<dyn Write>) -> Self {
         let env = Rc::new(RefCell::new(Environment::new(None)));
-        Interpreter {
+        let interpreter = Interpreter {
             globals: env.clone(),
-            environment: env.clone(),
-            is_REPL: RefCell::new(is_REPL),
-            is_looping: RefCell::new(false),
+            environment: RefCell::new(env),
+            builtins: Rc::new(RefCell::new(Environment::new(None))),
+            is_repl: RefCell::new(is_repl),
+            echo: RefCell::new(Type::repr),
+            position: RefCell::new(Span::default()),
+            failure: RefCell::new(None),
+            error_format: RefCell::new(ErrorFormat::Text),
+            thrown: RefCell::new(HashMap::new()),
+            next_thrown: RefCell::new(0),
+            current_file: RefCell::new(None),
+            search_paths: RefCell::new(Vec::new()),
+            modules: RefCell::new(HashMap::new()),
+            loading: RefCell::new(Vec::new()),
+            input: RefCell::new(None),
+            out: RefCell::new(out),
+            err: RefCell::new(err),
+        };
+        natives::define_builtins(&interpreter);
+
+        interpreter
+    }
+
+    // scan, parse and execute a whole program.
+    pub fn run(&self, source: &str) -> Result<()> {
+        self.failure.take();
+        let result = self.execute_source(source);
+
+        self.settle(self.locate(result))
+    }
+
+    fn execute_source(&self, source: &str) -> Result<()> {
+        let scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens()?;
+
+        let parser = Parser::new(&tokens);
+        let stmts = parser.parse()?;
+
+        self.interpret(&stmts)
+    }
+
+    pub fn run_file(&self, path: &Path) -> Result<()> {
+        let io_error = |e: std::io::Error| MyError::IoError(format!("{}: {}", path.display(), e));
+        let source = fs::read_to_string(path).map_err(io_error)?;
+        let canonical = path.canonicalize().map_err(io_error)?;
+
+        let previous = self.current_file.replace(Some(canonical.clone()));
+        self.loading.borrow_mut().push(canonical);
+        let result = self.run(&source);
+        self.loading.borrow_mut().pop();
+        self.current_file.replace(previous);
+
+        result
+    }
+
+    // the `args` list seen by scripts.
+    pub fn set_args<S: AsRef<str>>(&self, args: &[S]) {
+        let args = args
+            .iter()
+            .map(|arg| Type::String(arg.as_ref().to_string()))
+            .collect();
+        self.define_builtin("args", &Type::List(Rc::new(RefCell::new(args))));
+    }
+
+    pub fn flush(&self) -> std::io::Result<()> {
+        self.out.borrow_mut().flush()?;
+        self.err.borrow_mut().flush()
+    }
+
+    // where readLine() and readAll() take their input from. without it they read stdin,
+    // except in the REPL, where stdin is the session's own input.
+    pub fn set_input(&self, input: Box<dyn BufRead>) {
+        self.input.replace(Some(input));
+    }
+
+    // one line including its terminator, empty at end of input.
+    pub(crate) fn read_line(&self) -> std::io::Result<String> {
+        let mut line = String::new();
+        match &mut *self.input.borrow_mut() {
+            Some(input) => input.read_line(&mut line)?,
+            None if self.is_repl() => return Err(repl_stdin()),
+            None => std::io::stdin().read_line(&mut line)?,
+        };
+        Ok(line)
+    }
+
+    pub(crate) fn read_all(&self) -> std::io::Result<String> {
+        let mut contents = String::new();
+        match &mut *self.input.borrow_mut() {
+            Some(input) => input.read_to_string(&mut contents)?,
+            None if self.is_repl() => return Err(repl_stdin()),
+            None => std::io::stdin().read_to_string(&mut contents)?,
+        };
+        Ok(contents)
+    }
+
+    // registers readFile, listDir and exists. scripts may read anywhere when `dirs` is
+    // empty, otherwise only below one of `dirs`.
+    pub fn allow_read<P: AsRef<Path>>(&self, dirs: &[P]) -> Result<()> {
+        natives::fs::define_read(self, natives::fs::scope(dirs)?);
+        Ok(())
+    }
+
+    // registers writeFile, appendFile and remove, scoped like `allow_read`.
+    pub fn allow_write<P: AsRef<Path>>(&self, dirs: &[P]) -> Result<()> {
+        natives::fs::define_write(self, natives::fs::scope(dirs)?);
+        Ok(())
+    }
+
+    // extra directories searched by `import` after the importing file's own directory.
+    pub fn add_search_path(&self, path: &Path) {
+        self.search_paths.borrow_mut().push(path.to_path_buf());
+    }
+
+    // evaluate a single expression, e.g. "a + 1", to its value.
+    pub fn eval(&self, source: &str) -> Result<Type> {
+        self.failure.take();
+        let scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens()?;
+
+        let parser = Parser::new(&tokens);
+        let expr = parser.parse_expression()?;
+
+        self.settle(self.evaluate_expr(&expr))
+    }
+
+    // nothing catches an error once it leaves run or eval, so the values thrown on its
+    // way out are dropped; the error keeps their message.
+    fn settle<T>(&self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            self.thrown.borrow_mut().clear();
+        }
+
+        result
+    }
+
+    // forgets the value behind `error` when it will never be caught.
+    fn discard_thrown(&self, error: &anyhow::Error) {
+        if let Some(MyError::ThrownError(id, _)) = error.downcast_ref::<MyError>() {
+            self.thrown.borrow_mut().remove(id);
         }
     }
 
+    // values thrown and not yet caught or dropped.
+    #[cfg(test)]
+    pub(crate) fn thrown_count(&self) -> usize {
+        self.thrown.borrow().len()
+    }
+
+    pub fn set_echo(&self, format: fn(&Type) -> String) {
+        self.echo.replace(format);
+    }
+
+    pub fn set_error_format(&self, format: ErrorFormat) {
+        self.error_format.replace(format);
+    }
+
+    // `error` located at the file and token where it was raised.
+    pub fn diagnostic(&self, error: &anyhow::Error) -> Diagnostic {
+        self.failure.take().unwrap_or_else(|| self.located(error))
+    }
+
+    pub fn report_error(&self, error: &anyhow::Error) {
+        let diagnostic = self.diagnostic(error);
+        self.report(error, &diagnostic);
+    }
+
+    // writes `error` in the configured format, described by `diagnostic` for JSON.
+    pub fn report(&self, error: &anyhow::Error, diagnostic: &Diagnostic) {
+        // nowhere left to report to if this fails.
+        let _ = self.emit(error, diagnostic);
+    }
+
+    // a warning about no script in particular, e.g. a mistyped REPL command.
+    pub fn report_warning(&self, code: &str, message: &str) {
+        let _ = self.emit(message, &Diagnostic::warning(code, message, None));
+    }
+
+    fn warn(&self, token: &Token, code: &str, message: &str) -> Result<()> {
+        let mut diagnostic = Diagnostic::warning(code, message, Some(Span::of(token)));
+        diagnostic.file = self.current_file.borrow().clone();
+        let text = format!("[line {}] Warning: {}", token.line, message);
+        self.emit(text, &diagnostic)?;
+
+        Ok(())
+    }
+
+    // `text` or `diagnostic`, whichever the error format asks for.
+    fn emit(&self, text: impl std::fmt::Display, diagnostic: &Diagnostic) -> std::io::Result<()> {
+        let mut err = self.err.borrow_mut();
+        match *self.error_format.borrow() {
+            ErrorFormat::Text => writeln!(err, "{}", text)?,
+            ErrorFormat::Json => writeln!(err, "{}", diagnostic.to_json())?,
+        }
+
+        err.flush()
+    }
+
+    fn located(&self, error: &anyhow::Error) -> Diagnostic {
+        let mut diagnostic = Diagnostic::error(error);
+        diagnostic.file = self.current_file.borrow().clone();
+        let position = *self.position.borrow();
+        if diagnostic.span.is_none() && position.line > 0 {
+            diagnostic.span = Some(position);
+        }
+
+        diagnostic
+    }
+
+    // remembers where an error leaving the current file came from, unless an inner
+    // file already did.
+    fn locate<T>(&self, result: Result<T>) -> Result<T> {
+        if let Err(error) = &result {
+            if self.failure.borrow().is_none() {
+                self.failure.replace(Some(self.located(error)));
+            }
+        }
+
+        result
+    }
+
+    fn set_position(&self, token: &Token) {
+        self.position.replace(Span::of(token));
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Type> {
+        self.globals
+            .borrow()
+            .get_value(name)
+            .or_else(|| self.builtins.borrow().get_value(name))
+    }
+
+    // every name visible from the current scope, builtins included, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.environment.borrow().borrow().names();
+        names.extend(self.globals.borrow().names());
+        names.extend(self.builtins.borrow().names());
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    // what the current scope and those around it hold; builtins are not included.
+    pub fn bindings(&self) -> Vec<(String, Option<Type>)> {
+        self.environment.borrow().borrow().bindings()
+    }
+
+    pub fn set_global(&self, name: &str, value: Type) {
+        self.globals.borrow().define(name, &value);
+    }
+
+    // natives live in their own scope so that scripts can shadow them.
+    pub fn register_native<F>(&self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&Interpreter, &[Type]) -> Result<Type> + 'static,
+    {
+        let native = Type::Native(NativeFunction::new(name, arity, function));
+        self.define_builtin(name, &native);
+    }
+
+    // builtins sit behind every scope, so scripts may shadow them.
+    pub(crate) fn define_builtin(&self, name: &str, value: &Type) {
+        self.builtins.borrow().define(name, value);
+    }
+
     pub fn interpret(&self, stmts: &[Stmt]) -> Result<()> {
         for stmt in stmts {
-            self.evaluate_stmt(stmt)?;
+            match self.evaluate_stmt(stmt)? {
+                Flow::Normal => {}
+                Flow::Break => return Err(MyError::BreakNotInLoop.into()),
+                Flow::Return(_) => return Err(MyError::ReturnNotInFunction.into()),
+            }
         }
 
         Ok(())
     }
 
-    // Result<is_break>
-    fn evaluate_stmt(&self, stmt: &Stmt) -> Result<bool> {
+    // line of the expression or statement evaluated last.
+    pub fn current_line(&self) -> usize {
+        self.position.borrow().line
+    }
+
+    fn evaluate_stmt(&self, stmt: &Stmt) -> Result<Flow> {
         match stmt {
             Stmt::ExprStmt(expr) => {
                 let result = self.evaluate_expr(expr)?;
-                if self.is_REPL() {
-                    println!("{}", result);
+                if self.is_repl() {
+                    let echo = *self.echo.borrow();
+                    writeln!(self.out.borrow_mut(), "{}", echo(&result))?;
                 }
             }
             Stmt::PrintStmt(expr) => {
                 let value = self.evaluate_expr(expr)?;
-                println!("{}", value);
+                writeln!(self.out.borrow_mut(), "{}", value)?;
             }
             var @ Stmt::VarStmt {
                 name: _,
@@ -53,8 +368,7 @@ impl Interpreter {
                 self.define_var_stmt(var)?;
             }
             Stmt::Block(vec) => {
-                let new_environment = Environment::new(None);
-                self.execute_block(vec, new_environment)?;
+                return self.execute_block(vec, self.new_scope());
             }
             Stmt::IfStmt {
                 condition,
@@ -63,31 +377,123 @@ impl Interpreter {
             } => {
                 let expr_result = self.evaluate_expr(condition)?;
                 if self.is_truthy(&expr_result) {
-                    self.evaluate_stmt(then_branch)?;
+                    return self.evaluate_stmt(then_branch);
                 } else {
-                    self.evaluate_stmt(else_branch)?;
+                    return self.evaluate_stmt(else_branch);
                 }
             }
             Stmt::WhileStmt { condition, body } => {
-                *self.is_looping.borrow_mut() = true;
-                while self.is_truthy(&self.evaluate_expr(condition)?) && *self.is_looping.borrow() {
-                    self.evaluate_stmt(body)?;
+                while self.is_truthy(&self.evaluate_expr(condition)?) {
+                    match self.evaluate_stmt(body)? {
+                        Flow::Normal => {}
+                        Flow::Break => break,
+                        flow @ Flow::Return(_) => return Ok(flow),
+                    }
                 }
-                *self.is_looping.borrow_mut() = false;
             }
-            Stmt::Break => {
-                if !*self.is_looping.borrow() {
-                    return Err(MyError::BreakNotInLoop.into());
+            Stmt::Break { .. } => {
+                return Ok(Flow::Break);
+            }
+            Stmt::Return { keyword, value } => {
+                self.set_position(keyword);
+                return Ok(Flow::Return(self.evaluate_expr(value)?));
+            }
+            Stmt::Throw { keyword, value } => {
+                self.set_position(keyword);
+                let value = self.evaluate_expr(value)?;
+                let message = value.to_string();
+
+                let id = *self.next_thrown.borrow();
+                *self.next_thrown.borrow_mut() += 1;
+                self.thrown.borrow_mut().insert(id, value);
+                return Err(MyError::ThrownError(id, message).into());
+            }
+            Stmt::Try {
+                body,
+                catch_clause,
+                finally_body,
+            } => {
+                let result = match (self.execute_block(body, self.new_scope()), catch_clause) {
+                    (Err(error), Some(clause)) if !is_exit(&error) => {
+                        self.failure.take();
+                        let environment = self.new_scope();
+                        environment.define(&clause.name.lexeme, &self.error_value(error));
+                        self.execute_block(&clause.body, environment)
+                    }
+                    (result, _) => result,
+                };
+                // exit() skips finally as well.
+                if matches!(&result, Err(error) if is_exit(error)) {
+                    return result;
+                }
+
+                if let Some(finally_body) = finally_body {
+                    // break, return or an error inside finally replaces the pending outcome.
+                    let pending = self.failure.take();
+                    let outcome = self.execute_block(finally_body, self.new_scope());
+                    if !matches!(outcome, Ok(Flow::Normal)) {
+                        if let Err(error) = &result {
+                            self.discard_thrown(error);
+                        }
+                        return outcome;
+                    }
+                    self.failure.replace(pending);
+                }
+
+                return result;
+            }
+            Stmt::Import {
+                keyword,
+                path,
+                name,
+            } => {
+                self.set_position(keyword);
+                let module = self.import_module(path, name)?;
+                self.environment.borrow().borrow().define(name, &module);
+            }
+            Stmt::Match {
+                keyword,
+                value,
+                arms,
+            } => {
+                self.set_position(keyword);
+                let value = self.evaluate_expr(value)?;
+                for arm in arms {
+                    let mut bindings = Vec::new();
+                    if !self.match_pattern(&arm.pattern, &value, &mut bindings) {
+                        continue;
+                    }
+
+                    // bindings are scoped to the guard and body of their arm.
+                    let environment = self.new_scope();
+                    for (name, bound) in &bindings {
+                        environment.define(name, bound);
+                    }
+                    let matched = self.in_scope(environment, || {
+                        if let Some(guard) = &arm.guard {
+                            if !self.is_truthy(&self.evaluate_expr(guard)?) {
+                                return Ok(None);
+                            }
+                        }
+                        Ok(Some(self.evaluate_stmt(&arm.body)?))
+                    })?;
+
+                    if let Some(flow) = matched {
+                        return Ok(flow);
+                    }
                 }
-                *self.is_looping.borrow_mut() = false;
-                return Ok(true);
+
+                let message = format!("no match arm for value {}.", value);
+                self.warn(keyword, "NoMatchArm", &message)?;
             }
-            fun @ Stmt::Function { name, params, body } => {
+            fun @ Stmt::Function { name, .. } => {
                 let function = Type::Fun(Box::new(LoxFunction {
                     declaration: fun.clone(),
+                    closure: self.environment.borrow().clone(),
                 }));
                 self.environment
-                    .borrow_mut()
+                    .borrow()
+                    .borrow()
                     .define(&name.lexeme, &function);
             }
             Stmt::NULL => {
@@ -95,36 +501,215 @@ impl Interpreter {
             }
         };
 
-        Ok(false)
+        Ok(Flow::Normal)
     }
 
-    pub fn execute_block(&self, statements: &[Stmt], new_environment: Environment) -> Result<()> {
-        // [Attention] Be careful when swapping env. Bad things can happen.
-        // swap in the new environment.
-        let pre_env = Rc::new(RefCell::new(self.environment.replace(new_environment)));
-        self.set_env(pre_env.clone());
+    pub fn execute_block(&self, statements: &[Stmt], new_environment: Environment) -> Result<Flow> {
+        self.in_scope(new_environment, || {
+            for stmt in statements {
+                let flow = self.evaluate_stmt(stmt)?;
+                if flow != Flow::Normal {
+                    return Ok(flow);
+                }
+            }
+
+            Ok(Flow::Normal)
+        })
+    }
+
+    // executes a module file once and caches its namespace.
+    fn import_module(&self, target: &str, name: &str) -> Result<Type> {
+        let import = *self.position.borrow();
+        let base_dir = match &*self.current_file.borrow() {
+            Some(file) => file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => PathBuf::from("."),
+        };
+        let path = module::resolve(target, &base_dir, &self.search_paths.borrow())
+            .ok_or_else(|| MyError::ModuleNotFoundError(target.to_string()))?;
+
+        if let Some(module) = self.modules.borrow().get(&path) {
+            return Ok(Type::Module(module.clone()));
+        }
+        if let Some(start) = self.loading.borrow().iter().position(|p| *p == path) {
+            let chain = self.loading.borrow()[start..]
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>();
+            return Err(MyError::ImportCycleError(chain.join(" -> ")).into());
+        }
+
+        let source = fs::read_to_string(&path)
+            .map_err(|e| MyError::IoError(format!("{}: {}", path.display(), e)))?;
 
-        for stmt in statements {
-            match self.evaluate_stmt(stmt) {
-                Ok(true) => break,
-                Ok(_) => continue,
-                Err(e) => return Err(e.into()),
+        // modules get their own top-level scope; builtins stay visible.
+        let environment = Rc::new(RefCell::new(Environment::new(None)));
+        let previous = self.current_file.replace(Some(path.clone()));
+        self.loading.borrow_mut().push(path.clone());
+        let result = self.in_environment(environment.clone(), || self.execute_source(&source));
+        let result = self.locate(result);
+        self.loading.borrow_mut().pop();
+        self.current_file.replace(previous);
+        if result.is_err() {
+            let importer = match &*self.current_file.borrow() {
+                Some(file) => file.display().to_string(),
+                None => "<script>".to_string(),
+            };
+            if let Some(failure) = &mut *self.failure.borrow_mut() {
+                failure
+                    .notes
+                    .push(format!("imported from {}:{}", importer, import.line));
             }
         }
+        result?;
 
-        // swap back.
-        self.environment.swap(&pre_env);
+        let module = Rc::new(Module {
+            name: name.to_string(),
+            path: path.clone(),
+            environment,
+        });
+        self.modules.borrow_mut().insert(path, module.clone());
 
-        Ok(())
+        Ok(Type::Module(module))
+    }
+
+    // the value a catch block sees: thrown values as they are, runtime errors as error objects.
+    fn error_value(&self, error: anyhow::Error) -> Type {
+        let kind = match error.downcast_ref::<MyError>() {
+            Some(MyError::ThrownError(id, _)) => {
+                if let Some(value) = self.thrown.borrow_mut().remove(id) {
+                    return value;
+                }
+                "ThrownError"
+            }
+            Some(e) => e.name(),
+            None => "Error",
+        };
+
+        Type::Error(Box::new(ErrorValue {
+            kind: kind.to_string(),
+            message: error.to_string(),
+            line: self.current_line(),
+        }))
+    }
+
+    fn get_property(&self, object: &Type, name: &Token) -> Result<Type> {
+        match (object, name.lexeme.as_str()) {
+            (Type::Error(e), "message") => Ok(Type::String(e.message.clone())),
+            (Type::Error(e), "kind") => Ok(Type::String(e.kind.clone())),
+            (Type::Error(e), "line") => Ok(Type::Number(e.line as f64)),
+            (Type::Module(m), _) => m
+                .environment
+                .borrow()
+                .get_value(&name.lexeme)
+                .ok_or_else(|| MyError::UndefinedPropertyError(name.lexeme.clone()).into()),
+            _ => Err(MyError::UndefinedPropertyError(name.lexeme.clone()).into()),
+        }
+    }
+
+    // a fresh scope nested in the current one.
+    fn new_scope(&self) -> Environment {
+        Environment::new(Some(self.environment.borrow().clone()))
+    }
+
+    // run `f` with `new_environment` as the innermost scope.
+    fn in_scope<T, F>(&self, new_environment: Environment, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        self.in_environment(Rc::new(RefCell::new(new_environment)), f)
+    }
+
+    fn in_environment<T, F>(&self, environment: Rc<RefCell<Environment>>, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let previous = self.environment.replace(environment);
+
+        let result = f();
+
+        // swap back, on errors too.
+        self.environment.replace(previous);
+
+        result
+    }
+
+    // on success `bindings` holds the names bound by the pattern.
+    fn match_pattern(
+        &self,
+        pattern: &Pattern,
+        value: &Type,
+        bindings: &mut Vec<(String, Type)>,
+    ) -> bool {
+        match pattern {
+            Pattern::Literal(literal) => self.is_equal(literal, value),
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => match value {
+                Type::Number(n) if *inclusive => start <= n && n <= end,
+                Type::Number(n) => start <= n && n < end,
+                _ => false,
+            },
+            Pattern::Wildcard => true,
+            Pattern::Binding(name) => {
+                bindings.push((name.lexeme.clone(), value.clone()));
+                true
+            }
+            Pattern::Alternative(alternatives) => alternatives.iter().any(|alternative| {
+                let len = bindings.len();
+                let matched = self.match_pattern(alternative, value, bindings);
+                if !matched {
+                    bindings.truncate(len);
+                }
+                matched
+            }),
+            Pattern::List { elements, rest } => {
+                let items = match value {
+                    Type::List(items) => items.borrow().clone(),
+                    _ => return false,
+                };
+                let fits = match rest {
+                    Some(_) => items.len() >= elements.len(),
+                    None => items.len() == elements.len(),
+                };
+                if !fits {
+                    return false;
+                }
+
+                for (element, item) in elements.iter().zip(items.iter()) {
+                    if !self.match_pattern(element, item, bindings) {
+                        return false;
+                    }
+                }
+                match rest {
+                    Some(rest) => {
+                        let tail = items[elements.len()..].to_vec();
+                        self.match_pattern(rest, &Type::List(Rc::new(RefCell::new(tail))), bindings)
+                    }
+                    None => true,
+                }
+            }
+        }
     }
 
     fn define_var_stmt(&self, stmt: &Stmt) -> Result<()> {
-        let (name, value) = match stmt {
-            Stmt::VarStmt { name, initializer } => (name, self.evaluate_expr(initializer)?),
+        let (name, initializer) = match stmt {
+            Stmt::VarStmt { name, initializer } => (name, initializer),
             _ => panic!("should not be here."),
         };
 
-        self.environment.borrow_mut().define(&name.lexeme, &value);
+        match initializer {
+            Expr::Null => self.environment.borrow().borrow().declare(&name.lexeme),
+            _ => {
+                let value = self.evaluate_expr(initializer)?;
+                self.environment
+                    .borrow()
+                    .borrow()
+                    .define(&name.lexeme, &value);
+            }
+        }
         //println!("{:?}", self.environment);
 
         Ok(())
@@ -133,7 +718,12 @@ impl Interpreter {
     fn get_var_expr(&self, expr: &Expr) -> Result<Type> {
         //println!("{:?}", self.environment);
         let value = match expr {
-            Expr::Var(ref token) => self.environment.borrow().get(token)?,
+            Expr::Var(ref token) => self.environment.borrow().borrow().get(token).or_else(|e| {
+                match e.downcast_ref::<MyError>() {
+                    Some(MyError::EnValueNotFoundError(_)) => self.builtins.borrow().get(token),
+                    _ => Err(e),
+                }
+            })?,
             _ => panic!("should not be here."),
         };
 
@@ -142,67 +732,29 @@ impl Interpreter {
 
     pub fn evaluate_expr(&self, expr: &Expr) -> Result<Type> {
         use Expr::*;
+        if let Some(token) = expr.token() {
+            self.set_position(token);
+        }
         match expr {
             Literal(value) => Ok(value.clone()),
             Binary { left, op, right } => {
                 let left = self.evaluate_expr(left)?;
                 let right = self.evaluate_expr(right)?;
-                match op.token_type {
-                    // a bit ugly. refactor this.
-                    TokenType::MINUS
-                    | TokenType::SLASH
-                    | TokenType::STAR
-                    | TokenType::GREATER
-                    | TokenType::GreaterEqual
-                    | TokenType::LESS
-                    | TokenType::LessEqual => {
-                        let lnum = self.get_number(&left, op.line)?;
-                        let rnum = self.get_number(&right, op.line)?;
-
-                        match op.token_type {
-                            TokenType::MINUS => Ok(Type::Number(lnum - rnum)),
-                            TokenType::SLASH => {
-                                if rnum == 0.0 {
-                                    return Err(MyError::DividedbyzeroError.into());
-                                }
-                                Ok(Type::Number(lnum / rnum))
-                            }
-                            TokenType::STAR => Ok(Type::Number(lnum * rnum)),
-                            TokenType::GREATER => Ok(Type::Bool(lnum > rnum)),
-                            TokenType::GreaterEqual => Ok(Type::Bool(lnum >= rnum)),
-                            TokenType::LESS => Ok(Type::Bool(lnum < rnum)),
-                            TokenType::LessEqual => Ok(Type::Bool(lnum <= rnum)),
-                            _ => panic!("never reach this."),
-                        }
-                    }
-                    TokenType::BangEqual => Ok(Type::Bool(!self.is_equal(&left, &right))),
-                    TokenType::EqualEqual => Ok(Type::Bool(self.is_equal(&left, &right))),
-                    TokenType::PLUS => {
-                        // 1. add num 2. concat strings.
-                        match (left, right) {
-                            (Type::Number(lnum), Type::Number(rnum)) => {
-                                Ok(Type::Number(lnum + rnum))
-                            }
-                            (Type::String(lstr), Type::String(rstr)) => {
-                                Ok(Type::String(format!("{}{}", lstr, rstr)))
-                            }
-                            _ => {
-                                let line = op.line;
-                                Err(MyError::CastError(format!(
-                                    "[Line {line}]: Operation not supported."
-                                ))
-                                .into())
-                            }
-                        }
-                    }
-                    _ => {
-                        panic!("TODO Binary op")
-                    }
-                }
+                // errors belong to the operator, not to the operand evaluated last.
+                self.set_position(op);
+                self.binary_op(op.token_type, op.line, left, right)
             }
             Grouping(expr) => self.evaluate_expr(expr),
+            List(elements) => {
+                let items = elements
+                    .iter()
+                    .map(|x| self.evaluate_expr(x))
+                    .collect::<Result<Vec<Type>>>()?;
+                Ok(Type::List(Rc::new(RefCell::new(items))))
+            }
             Unary { op, right } => {
                 let right = self.evaluate_expr(right)?;
+                self.set_position(op);
                 match op.token_type {
                     TokenType::BANG => Ok(Type::Bool(!self.is_truthy(&right))),
                     TokenType::MINUS => {
@@ -210,6 +762,10 @@ impl Interpreter {
                         let num = self.get_number(&right, op.line)?;
                         Ok(Type::Number(-num))
                     }
+                    TokenType::TILDE => {
+                        let num = self.get_integer(&right, op.line)?;
+                        Ok(Type::Number(!num as f64))
+                    }
                     _ => todo!(),
                 }
             }
@@ -217,9 +773,35 @@ impl Interpreter {
             var @ Var(_) => Ok(self.get_var_expr(var)?),
             Assign { name, value } => {
                 let value = self.evaluate_expr(value)?;
-                self.environment.borrow_mut().assign(&name, &value)?;
+                self.environment.borrow().borrow().assign(name, &value)?;
                 Ok(value)
             }
+            CompoundAssign { target, op, value } => {
+                let op_type = match op.token_type {
+                    TokenType::PlusEqual => TokenType::PLUS,
+                    TokenType::MinusEqual => TokenType::MINUS,
+                    TokenType::StarEqual => TokenType::STAR,
+                    TokenType::SlashEqual => TokenType::SLASH,
+                    TokenType::PercentEqual => TokenType::PERCENT,
+                    _ => panic!("not compound assignment Operand"),
+                };
+                let (_, new) = self.update_target(target, op, |current| {
+                    let value = self.evaluate_expr(value)?;
+                    self.binary_op(op_type, op.line, current, value)
+                })?;
+                Ok(new)
+            }
+            Increment { target, op, prefix } => {
+                let delta = match op.token_type {
+                    TokenType::PlusPlus => 1.0,
+                    _ => -1.0,
+                };
+                let (old, new) = self.update_target(target, op, |current| {
+                    let num = self.get_number(&current, op.line)?;
+                    Ok(Type::Number(num + delta))
+                })?;
+                Ok(if *prefix { new } else { old })
+            }
             Logical { left, op, right } => {
                 // short circut.
                 let left = self.evaluate_expr(left)?;
@@ -227,16 +809,16 @@ impl Interpreter {
                 match op.token_type {
                     TokenType::OR => {
                         if self.is_truthy(&left) {
-                            return Ok(left);
+                            Ok(left)
                         } else {
-                            return self.evaluate_expr(right);
+                            self.evaluate_expr(right)
                         }
                     }
                     TokenType::AND => {
                         if !self.is_truthy(&left) {
-                            return Ok(left);
+                            Ok(left)
                         } else {
-                            return self.evaluate_expr(right);
+                            self.evaluate_expr(right)
                         }
                     }
                     _ => {
@@ -244,6 +826,18 @@ impl Interpreter {
                     }
                 }
             }
+            Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                // only the selected branch is evaluated.
+                if self.is_truthy(&self.evaluate_expr(condition)?) {
+                    self.evaluate_expr(then_branch)
+                } else {
+                    self.evaluate_expr(else_branch)
+                }
+            }
             Call {
                 callee,
                 paren,
@@ -254,13 +848,129 @@ impl Interpreter {
                     .iter()
                     .map(|x| self.evaluate_expr(x))
                     .collect::<Result<Vec<Type>>>()?;
-                let _ = match callee {
-                    Type::Fun(func) => func.call(self, &arguments),
+                self.set_position(paren);
+                let function: &dyn Callable = match callee {
+                    Type::Fun(ref func) => func.as_ref(),
+                    Type::Native(ref func) => func,
                     _ => return Err(MyError::NotCallableError.into()),
                 };
 
-                //TODO
-                Ok(Type::Nil)
+                let arity = function.arity()?;
+                if arguments.len() != arity {
+                    return Err(MyError::ArityMismatchError(arity, arguments.len()).into());
+                }
+
+                function.call(self, &arguments)
+            }
+            Get { object, name } => {
+                let object = self.evaluate_expr(object)?;
+                self.get_property(&object, name)
+            }
+        }
+    }
+
+    // read-modify-write of an assignable target, returning (old, new).
+    fn update_target<F>(&self, target: &Expr, op: &Token, update: F) -> Result<(Type, Type)>
+    where
+        F: FnOnce(Type) -> Result<Type>,
+    {
+        match target {
+            Expr::Var(name) => {
+                let old = self.get_var_expr(target)?;
+                let new = update(old.clone())?;
+                self.environment.borrow().borrow().assign(name, &new)?;
+                Ok((old, new))
+            }
+            _ => Err(MyError::InvalidAssignmentTargetError(SyntaxError::at(
+                op,
+                "Invalid assignment target.",
+            ))
+            .into()),
+        }
+    }
+
+    fn binary_op(&self, op_type: TokenType, line: usize, left: Type, right: Type) -> Result<Type> {
+        match op_type {
+            // a bit ugly. refactor this.
+            TokenType::MINUS
+            | TokenType::SLASH
+            | TokenType::STAR
+            | TokenType::PERCENT
+            | TokenType::StarStar
+            | TokenType::GREATER
+            | TokenType::GreaterEqual
+            | TokenType::LESS
+            | TokenType::LessEqual => {
+                let lnum = self.get_number(&left, line)?;
+                let rnum = self.get_number(&right, line)?;
+
+                match op_type {
+                    TokenType::MINUS => Ok(Type::Number(lnum - rnum)),
+                    TokenType::SLASH => {
+                        if rnum == 0.0 {
+                            return Err(MyError::DividedbyzeroError.into());
+                        }
+                        Ok(Type::Number(lnum / rnum))
+                    }
+                    TokenType::STAR => Ok(Type::Number(lnum * rnum)),
+                    TokenType::PERCENT => {
+                        if rnum == 0.0 {
+                            return Err(MyError::DividedbyzeroError.into());
+                        }
+                        Ok(Type::Number(lnum % rnum))
+                    }
+                    TokenType::StarStar => Ok(Type::Number(lnum.powf(rnum))),
+                    TokenType::GREATER => Ok(Type::Bool(lnum > rnum)),
+                    TokenType::GreaterEqual => Ok(Type::Bool(lnum >= rnum)),
+                    TokenType::LESS => Ok(Type::Bool(lnum < rnum)),
+                    TokenType::LessEqual => Ok(Type::Bool(lnum <= rnum)),
+                    _ => panic!("never reach this."),
+                }
+            }
+            TokenType::AMPERSAND
+            | TokenType::PIPE
+            | TokenType::CARET
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => {
+                let lint = self.get_integer(&left, line)?;
+                let rint = self.get_integer(&right, line)?;
+
+                let result = match op_type {
+                    TokenType::AMPERSAND => lint & rint,
+                    TokenType::PIPE => lint | rint,
+                    TokenType::CARET => lint ^ rint,
+                    TokenType::LessLess | TokenType::GreaterGreater => {
+                        if !(0..64).contains(&rint) {
+                            let msg = format!("[Line {line}]: Shift amount out of range");
+                            return Err(MyError::CastError(msg).into());
+                        }
+                        if op_type == TokenType::LessLess {
+                            lint << rint
+                        } else {
+                            lint >> rint
+                        }
+                    }
+                    _ => panic!("never reach this."),
+                };
+                Ok(Type::Number(result as f64))
+            }
+            TokenType::BangEqual => Ok(Type::Bool(!self.is_equal(&left, &right))),
+            TokenType::EqualEqual => Ok(Type::Bool(self.is_equal(&left, &right))),
+            TokenType::PLUS => {
+                // 1. add num 2. concat strings.
+                match (left, right) {
+                    (Type::Number(lnum), Type::Number(rnum)) => Ok(Type::Number(lnum + rnum)),
+                    (Type::String(lstr), Type::String(rstr)) => {
+                        Ok(Type::String(format!("{}{}", lstr, rstr)))
+                    }
+                    _ => Err(MyError::CastError(format!(
+                        "[Line {line}]: Operation not supported."
+                    ))
+                    .into()),
+                }
+            }
+            _ => {
+                panic!("TODO Binary op")
             }
         }
     }
@@ -275,6 +985,18 @@ impl Interpreter {
         }
     }
 
+    // bitwise operands must be exact integers.
+    fn get_integer(&self, t: &Type, line: usize) -> Result<i64> {
+        let value = self.get_number(t, line)?;
+        if value.fract() != 0.0 || !(-9223372036854775808.0..9223372036854775808.0).contains(&value)
+        {
+            let msg = format!("[Line {line}]: Operand must be an integer");
+            return Err(MyError::CastError(msg).into());
+        }
+
+        Ok(value as i64)
+    }
+
     fn is_truthy(&self, t: &Type) -> bool {
         match *t {
             Type::Nil => false,
@@ -291,15 +1013,20 @@ impl Interpreter {
         }
     }
 
-    fn set_env(&self, environment: Rc<RefCell<Environment>>) {
-        self.environment.borrow_mut().set_env(environment);
+    pub fn get_environment(&self) -> Result<Rc<RefCell<Environment>>> {
+        Ok(self.environment.borrow().clone())
     }
 
-    pub fn get_environment(&self) -> Result<&RefCell<Environment>> {
-        Ok(&self.environment)
+    fn is_repl(&self) -> bool {
+        *self.is_repl.borrow()
     }
+}
 
-    fn is_REPL(&self) -> bool {
-        *self.is_REPL.borrow()
-    }
+fn is_exit(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<MyError>(), Some(MyError::Exit(_)))
+}
+
+// reading it would swallow the lines typed after the current one.
+fn repl_stdin() -> std::io::Error {
+    std::io::Error::other("in use by the REPL")
 }
@@ -0,0 +1,38 @@
+//! craftinginterpreters rust version, usable as a library.
+//!
+//! ```
+//! use myjlox::{Interpreter, Type};
+//!
+//! let interpreter = Interpreter::new(false);
+//! interpreter.run("var a = 1 + 2;").unwrap();
+//! assert_eq!(interpreter.eval("a * 2").unwrap(), Type::Number(6.0));
+//! ```
+#![allow(clippy::upper_case_acronyms, clippy::enum_variant_names)]
+
+pub mod checker;
+pub mod color;
+pub mod diagnostic;
+pub mod dump;
+pub mod environment;
+pub mod errors;
+pub mod expr;
+pub mod interpreter;
+pub mod module;
+mod natives;
+pub mod output;
+pub mod parser;
+pub mod pattern;
+pub mod scanner;
+pub mod stmt;
+#[cfg(test)]
+mod tests;
+pub mod tokens;
+
+pub use errors::MyError;
+pub use interpreter::Interpreter;
+pub use output::SharedBuffer;
+pub use stmt::{Callable, NativeFunction};
+pub use tokens::Type;
+
+#[macro_use]
+extern crate lazy_static;
@@ -1,91 +1,378 @@
-mod environment;
-mod errors;
-mod expr;
-mod interpreter;
-mod parser;
-mod scanner;
-mod stmt;
-#[cfg(test)]
-mod tests;
-mod tokens;
 use anyhow::Result;
-use clap::Parser;
-use interpreter::Interpreter;
-use log::{debug, error};
-use parser::Parser as MyParser;
-use scanner::Scanner;
+use clap::builder::{OsStringValueParser, TypedValueParser};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use myjlox::diagnostic::{Diagnostic, ErrorFormat};
+use myjlox::errors::exit_code;
+use myjlox::parser::Parser as LoxParser;
+use myjlox::scanner::Scanner;
+use myjlox::{checker, dump, Interpreter, MyError};
+use std::ffi::OsString;
 use std::fs;
-use std::io::{BufRead, Write};
-use std::path::PathBuf;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
 
-#[macro_use]
-extern crate lazy_static;
+mod repl;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(
+    author,
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    after_help = "Exit status: 0 on success, 65 for syntax errors, 70 for runtime errors,\n74 for I/O errors, or the code passed to exit(). A REPL session exits with the\nstatus of its last failed input."
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    // `myjlox FILE` is short for `myjlox run FILE`.
+    #[command(flatten)]
+    run: RunArgs,
+    /// How errors and warnings are written to stderr; json gives one object per line.
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true)]
+    error_format: Format,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a script, a snippet or stdin; the default.
+    Run(RunArgs),
+    /// Start the interactive prompt.
+    Repl(ReplArgs),
+    /// Print the scanner output with line and column positions.
+    Tokens(DumpArgs),
+    /// Print the syntax tree produced by the parser.
+    Ast(DumpArgs),
+    /// Scan, parse and statically check a program without running it.
+    Check(Program),
+}
+
+#[derive(Args, Debug, Default)]
+struct Source {
+    /// Script file, or `-` to read the program from stdin.
+    filename: Option<PathBuf>,
+    /// Use CODE instead of a script; positional arguments all go to `args`.
+    #[arg(short, long, value_name = "CODE")]
+    eval: Option<String>,
+}
+
+// a program that must be named, for the commands that only look at it.
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+struct Program {
+    /// Script file, or `-` to read the program from stdin.
     filename: Option<PathBuf>,
+    /// Use CODE instead of a script.
+    #[arg(short, long, value_name = "CODE")]
+    eval: Option<String>,
+}
+
+impl Program {
+    fn input(&self) -> Input {
+        Input::from_source(&Source {
+            filename: self.filename.clone(),
+            eval: self.eval.clone(),
+        })
+    }
+}
+
+#[derive(Args, Debug, Default)]
+struct RunArgs {
+    #[command(flatten)]
+    options: Options,
+    #[command(flatten)]
+    source: Source,
+    /// Arguments passed to the script as `args`.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct ReplArgs {
+    #[command(flatten)]
+    options: Options,
+}
+
+#[derive(Args, Debug)]
+struct DumpArgs {
+    #[command(flatten)]
+    program: Program,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Text,
+    Json,
+}
+
+// what a running script may do, for `run` and `repl`.
+#[derive(Args, Debug, Default)]
+struct Options {
+    /// Extra directory to search for imported modules, may be repeated.
+    #[arg(short = 'I', long = "include", value_name = "DIR")]
+    include: Vec<PathBuf>,
+    /// Let scripts read files, optionally only below DIR; may be repeated.
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        value_parser = anywhere()
+    )]
+    allow_read: Option<Vec<PathBuf>>,
+    /// Let scripts write and remove files, optionally only below DIR; may be repeated.
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        value_parser = anywhere()
+    )]
+    allow_write: Option<Vec<PathBuf>>,
+}
+
+// a bare --allow-read or --allow-write comes through as an empty path.
+fn anywhere() -> impl TypedValueParser<Value = PathBuf> {
+    OsStringValueParser::new().map(PathBuf::from)
+}
+
+// the directories to allow; none, i.e. anywhere, once the flag is given bare.
+fn scope(dirs: &[PathBuf]) -> &[PathBuf] {
+    if dirs.iter().any(|dir| dir.as_os_str().is_empty()) {
+        &[]
+    } else {
+        dirs
+    }
+}
+
+// where the program comes from.
+enum Input {
+    Eval(String),
+    Stdin,
+    File(PathBuf),
+    Repl,
+}
+
+impl Input {
+    fn from_source(source: &Source) -> Self {
+        match (&source.eval, &source.filename) {
+            (Some(code), _) => Input::Eval(code.clone()),
+            (None, Some(filename)) if filename.as_os_str() == "-" => Input::Stdin,
+            (None, Some(filename)) => Input::File(filename.clone()),
+            // piped input is a program, not a session.
+            (None, None) if !std::io::stdin().is_terminal() => Input::Stdin,
+            (None, None) => Input::Repl,
+        }
+    }
+
+    fn file(&self) -> Option<PathBuf> {
+        match self {
+            Input::File(filename) => Some(filename.clone()),
+            _ => None,
+        }
+    }
+
+    // the program text, for the modes that do not run it.
+    fn read(self) -> Result<String> {
+        match self {
+            Input::Eval(code) => Ok(code),
+            Input::File(filename) => fs::read_to_string(&filename)
+                .map_err(|e| MyError::IoError(format!("{}: {}", filename.display(), e)).into()),
+            Input::Stdin | Input::Repl => read_stdin(),
+        }
+    }
 }
 
 fn main() {
     env_logger::init();
 
-    let cli = Cli::parse();
-    match cli.filename {
-        None => {
-            let _ = run_prompt(); // ignore this result
+    let cli = Cli::parse_from(subcommand_first(std::env::args_os().collect()));
+    let command = cli.command.unwrap_or(Command::Run(cli.run));
+    let is_repl = match &command {
+        Command::Run(args) => matches!(Input::from_source(&args.source), Input::Repl),
+        Command::Repl(_) => true,
+        _ => false,
+    };
+    // errors from the interpreter know their file, the other commands name theirs here.
+    let file = match &command {
+        Command::Tokens(args) | Command::Ast(args) => args.program.input().file(),
+        Command::Check(program) => program.input().file(),
+        Command::Run(_) | Command::Repl(_) => None,
+    };
+    let format = cli.error_format;
+    let interpreter = new_interpreter(is_repl, format);
+    let result = match command {
+        Command::Run(args) => configure(&interpreter, &args).and_then(|_| {
+            let input = Input::from_source(&args.source);
+            run(&interpreter, input, &|| fresh(format, &args))
+        }),
+        Command::Repl(repl) => {
+            let args = RunArgs {
+                options: repl.options,
+                ..RunArgs::default()
+            };
+            configure(&interpreter, &args)
+                .and_then(|_| repl::run(&interpreter, &|| fresh(format, &args)))
         }
-        Some(filename) => {
-            if let Err(e) = run_file(filename) {
-                error!("{}", e);
-                std::process::exit(1);
+        Command::Tokens(args) => dump_tokens(&args),
+        Command::Ast(args) => dump_ast(&args),
+        Command::Check(program) => check(&interpreter, program.input()),
+    };
+
+    if let Err(e) = result {
+        fail(&interpreter, &e, file.as_deref());
+    }
+}
+
+// `fresh` makes the interpreter a REPL starts over with.
+fn run(
+    interpreter: &Interpreter,
+    input: Input,
+    fresh: &dyn Fn() -> Result<Interpreter>,
+) -> Result<()> {
+    match input {
+        Input::Eval(code) => interpreter.run(&code),
+        Input::Stdin => interpreter.run(&read_stdin()?),
+        Input::File(filename) => run_file(interpreter, filename),
+        Input::Repl => repl::run(interpreter, fresh),
+    }
+}
+
+fn dump_tokens(args: &DumpArgs) -> Result<()> {
+    let source = args.program.input().read()?;
+    let tokens = Scanner::new(&source).scan_tokens()?;
+    match args.format {
+        Format::Text => print!("{}", dump::tokens_text(&tokens)),
+        Format::Json => println!("{:#}", dump::tokens_json(&tokens)),
+    }
+
+    Ok(())
+}
+
+fn dump_ast(args: &DumpArgs) -> Result<()> {
+    let source = args.program.input().read()?;
+    let tokens = Scanner::new(&source).scan_tokens()?;
+    let stmts = LoxParser::new(&tokens).parse()?;
+    match args.format {
+        Format::Text => print!("{}", dump::ast_text(&stmts)),
+        Format::Json => println!("{:#}", dump::ast_json(&stmts)),
+    }
+
+    Ok(())
+}
+
+// reports every static error, the last one through the usual failure path.
+fn check(interpreter: &Interpreter, input: Input) -> Result<()> {
+    let file = input.file();
+    let source = input.read()?;
+    let tokens = Scanner::new(&source).scan_tokens()?;
+    let stmts = LoxParser::new(&tokens).parse()?;
+    let mut errors = checker::check(&stmts);
+    match errors.pop() {
+        Some(last) => {
+            for e in errors {
+                let mut diagnostic = Diagnostic::error(&e);
+                diagnostic.file = file.clone();
+                interpreter.report(&e, &diagnostic);
             }
+            Err(last)
         }
+        None => Ok(()),
     }
 }
 
-fn run_prompt() -> Result<()> {
-    let mut buf = String::new();
-    let interpreter = Interpreter::new(true);
-    loop {
-        print!("> ");
-        std::io::stdout().flush().unwrap();
-        buf.clear();
+// reports `e` unless the script asked to exit, then terminates with its status.
+fn fail(interpreter: &Interpreter, e: &anyhow::Error, file: Option<&Path>) -> ! {
+    if !matches!(e.downcast_ref::<MyError>(), Some(MyError::Exit(_))) {
+        let mut diagnostic = interpreter.diagnostic(e);
+        if diagnostic.file.is_none() {
+            diagnostic.file = file.map(Path::to_path_buf);
+        }
+        interpreter.report(e, &diagnostic);
+    }
+    let _ = interpreter.flush();
+    std::process::exit(exit_code(e));
+}
 
-        match std::io::stdin().lock().read_line(&mut buf) {
-            Ok(n) if n > 0 => {
-                if let Err(e) = run(&interpreter, &buf) {
-                    eprintln!("{}", e);
-                }
-            }
-            Err(e) => {
-                panic!("{}", e);
-            }
-            _ => break, // otherwise break.
+// options before a subcommand name make it a script name for the implicit `run`, so
+// leading --error-format flags are moved after the subcommand they precede.
+fn subcommand_first(args: Vec<OsString>) -> Vec<OsString> {
+    let mut flags = 1;
+    while let Some(arg) = args.get(flags).and_then(|arg| arg.to_str()) {
+        match arg {
+            "--error-format" => flags += 2,
+            _ if arg.starts_with("--error-format=") => flags += 1,
+            _ => break,
         }
     }
+    let Some(name) = args.get(flags).and_then(|arg| arg.to_str()) else {
+        return args;
+    };
+    if flags == 1 || Cli::command().find_subcommand(name).is_none() {
+        return args;
+    }
 
-    Ok(())
+    let mut args = args;
+    let subcommand = args.remove(flags);
+    args.insert(1, subcommand);
+    args
 }
 
-fn run_file(filepath: PathBuf) -> Result<()> {
-    let interpreter = Interpreter::new(false);
-    let contents = fs::read_to_string(filepath)?;
-    run(&interpreter, &contents)?; // eval contents.
-                                   //
-    Ok(())
+fn new_interpreter(is_repl: bool, format: Format) -> Interpreter {
+    let interpreter = Interpreter::new(is_repl);
+    interpreter.set_error_format(match format {
+        Format::Text => ErrorFormat::Text,
+        Format::Json => ErrorFormat::Json,
+    });
+
+    interpreter
+}
+
+// a REPL interpreter set up like the first one, for `:reset`.
+fn fresh(format: Format, args: &RunArgs) -> Result<Interpreter> {
+    let interpreter = new_interpreter(true, format);
+    configure(&interpreter, args)?;
+
+    Ok(interpreter)
 }
 
-fn run(interpreter: &Interpreter, source: &str) -> Result<()> {
-    let scanner = Scanner::new(source);
-    let tokens = scanner.scan_tokens()?;
-    //println!("{:#?}", &tokens);
+// applies search paths and file permissions from the command line.
+fn configure(interpreter: &Interpreter, args: &RunArgs) -> Result<()> {
+    let options = &args.options;
+    if args.source.eval.is_some() {
+        // there is no script, so the first positional is an argument too.
+        let first = args.source.filename.iter().map(|f| f.display().to_string());
+        interpreter.set_args(&first.chain(args.args.iter().cloned()).collect::<Vec<_>>());
+    } else {
+        interpreter.set_args(&args.args);
+    }
+    for path in &options.include {
+        interpreter.add_search_path(path);
+    }
+    if let Some(dirs) = &options.allow_read {
+        interpreter.allow_read(scope(dirs))?;
+    }
+    if let Some(dirs) = &options.allow_write {
+        interpreter.allow_write(scope(dirs))?;
+    }
 
-    let parser = MyParser::new(&tokens);
-    let stmts = parser.parse()?;
-    //println!("{:#?}", stmts);
+    Ok(())
+}
+
+// the whole of stdin is the program, so readLine() sees end of input.
+fn read_stdin() -> Result<String> {
+    let mut source = String::new();
+    std::io::stdin()
+        .read_to_string(&mut source)
+        .map_err(|e| MyError::IoError(format!("stdin: {}", e)))?;
+    Ok(source)
+}
 
-    interpreter.interpret(&stmts)?;
+fn run_file(interpreter: &Interpreter, filepath: PathBuf) -> Result<()> {
+    interpreter.run_file(&filepath)?; // eval contents.
 
     Ok(())
 }
@@ -0,0 +1,36 @@
+use crate::environment::Environment;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// namespace value bound by `import`, holding the module's top-level definitions.
+#[derive(Clone)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    pub environment: Rc<RefCell<Environment>>,
+}
+
+impl std::fmt::Debug for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.environment, &other.environment)
+    }
+}
+
+// looks next to the importing file first, then through the search paths in order.
+pub fn resolve(target: &str, base_dir: &Path, search_paths: &[PathBuf]) -> Option<PathBuf> {
+    std::iter::once(base_dir)
+        .chain(search_paths.iter().map(PathBuf::as_path))
+        .map(|dir| dir.join(target))
+        .find(|path| path.is_file())
+        .and_then(|path| path.canonicalize().ok())
+}
@@ -0,0 +1,105 @@
+use crate::errors::{ErrorValue, MyError};
+use crate::interpreter::Interpreter;
+use crate::tokens::Type;
+use anyhow::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod fs;
+mod io;
+mod math;
+mod strings;
+
+// builtins available to every script.
+pub fn define_builtins(interpreter: &Interpreter) {
+    interpreter.register_native("clock", 0, |_, _| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Type::Number(now.as_secs_f64()))
+    });
+
+    interpreter.register_native("str", 1, |_, arguments| {
+        Ok(Type::String(arguments[0].to_string()))
+    });
+
+    // as the REPL shows it, e.g. strings in quotes.
+    interpreter.register_native("repr", 1, |_, arguments| {
+        Ok(Type::String(arguments[0].repr()))
+    });
+
+    // error objects for scripts to throw.
+    interpreter.register_native("Error", 1, |interpreter, arguments| {
+        Ok(Type::Error(Box::new(ErrorValue {
+            kind: "Error".to_string(),
+            message: arguments[0].to_string(),
+            line: interpreter.current_line(),
+        })))
+    });
+
+    // integer division, truncating toward zero so that a == idiv(a, b) * b + a % b.
+    interpreter.register_native("idiv", 2, |_, arguments| {
+        let lnum = number_arg("idiv", arguments, 0)?;
+        let rnum = number_arg("idiv", arguments, 1)?;
+        if rnum == 0.0 {
+            return Err(MyError::DividedbyzeroError.into());
+        }
+        Ok(Type::Number((lnum / rnum).trunc()))
+    });
+
+    // command line arguments after the script name, set by the host.
+    interpreter.set_args::<&str>(&[]);
+
+    // nil when unset or not unicode.
+    interpreter.register_native("getenv", 1, |_, arguments| {
+        let name = string_arg("getenv", arguments, 0)?;
+        Ok(std::env::var(name).map_or(Type::Nil, Type::String))
+    });
+
+    // output is flushed here; the host decides how to terminate.
+    interpreter.register_native("exit", 1, |interpreter, arguments| {
+        let code = number_arg("exit", arguments, 0)?;
+        if code.fract() != 0.0 || code < i32::MIN as f64 || code > i32::MAX as f64 {
+            let msg = "exit(): argument 1 must be an integer".to_string();
+            return Err(MyError::CastError(msg).into());
+        }
+        let _ = interpreter.flush();
+        Err(MyError::Exit(code as i32).into())
+    });
+
+    strings::define(interpreter);
+    math::define(interpreter);
+    io::define(interpreter);
+}
+
+pub fn number_arg(name: &str, arguments: &[Type], index: usize) -> Result<f64> {
+    match arguments.get(index) {
+        Some(Type::Number(value)) => Ok(*value),
+        _ => {
+            let msg = format!("{name}(): argument {} must be a number", index + 1);
+            Err(MyError::CastError(msg).into())
+        }
+    }
+}
+
+pub fn string_arg<'a>(name: &str, arguments: &'a [Type], index: usize) -> Result<&'a str> {
+    match arguments.get(index) {
+        Some(Type::String(value)) => Ok(value),
+        _ => {
+            let msg = format!("{name}(): argument {} must be a string", index + 1);
+            Err(MyError::CastError(msg).into())
+        }
+    }
+}
+
+// a non-negative whole number, for indices and counts.
+pub fn index_arg(name: &str, arguments: &[Type], index: usize) -> Result<usize> {
+    let value = number_arg(name, arguments, index)?;
+    if value < 0.0 || value.fract() != 0.0 {
+        let msg = format!(
+            "{name}(): argument {} must be a non-negative integer",
+            index + 1
+        );
+        return Err(MyError::CastError(msg).into());
+    }
+    Ok(value as usize)
+}
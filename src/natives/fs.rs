@@ -0,0 +1,135 @@
+use super::string_arg;
+use crate::errors::MyError;
+use crate::interpreter::Interpreter;
+use crate::tokens::Type;
+use anyhow::Result;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// directories a script may touch; empty means unrestricted.
+pub type Scope = Rc<Vec<PathBuf>>;
+
+pub fn scope<P: AsRef<Path>>(dirs: &[P]) -> Result<Scope> {
+    let dirs = dirs
+        .iter()
+        .map(|dir| {
+            let dir = dir.as_ref();
+            dir.canonicalize().map_err(|e| io_error(dir, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Rc::new(dirs))
+}
+
+pub fn define_read(interpreter: &Interpreter, scope: Scope) {
+    let allowed = scope.clone();
+    interpreter.register_native("readFile", 1, move |_, arguments| {
+        let path = checked_path("readFile", arguments, &allowed, "read")?;
+        let contents = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+        Ok(Type::String(contents))
+    });
+
+    // entry names, sorted.
+    let allowed = scope.clone();
+    interpreter.register_native("listDir", 1, move |_, arguments| {
+        let path = checked_path("listDir", arguments, &allowed, "read")?;
+        let mut names = fs::read_dir(&path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                    .collect::<std::io::Result<Vec<_>>>()
+            })
+            .map_err(|e| io_error(&path, e))?;
+        names.sort();
+        let names = names.into_iter().map(Type::String).collect();
+        Ok(Type::List(Rc::new(RefCell::new(names))))
+    });
+
+    interpreter.register_native("exists", 1, move |_, arguments| {
+        let path = checked_path("exists", arguments, &scope, "read")?;
+        Ok(Type::Bool(path.exists()))
+    });
+}
+
+pub fn define_write(interpreter: &Interpreter, scope: Scope) {
+    let allowed = scope.clone();
+    interpreter.register_native("writeFile", 2, move |_, arguments| {
+        let path = checked_path("writeFile", arguments, &allowed, "write")?;
+        let contents = string_arg("writeFile", arguments, 1)?;
+        fs::write(&path, contents).map_err(|e| io_error(&path, e))?;
+        Ok(Type::Nil)
+    });
+
+    let allowed = scope.clone();
+    interpreter.register_native("appendFile", 2, move |_, arguments| {
+        use std::io::Write;
+
+        let path = checked_path("appendFile", arguments, &allowed, "write")?;
+        let contents = string_arg("appendFile", arguments, 1)?;
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .map_err(|e| io_error(&path, e))?;
+        Ok(Type::Nil)
+    });
+
+    // removes a file or an empty directory.
+    interpreter.register_native("remove", 1, move |_, arguments| {
+        let path = checked_path("remove", arguments, &scope, "write")?;
+        // the scope may hold anything below its directories, not the directories.
+        if scope.contains(&path) {
+            let msg = format!("remove(): no write access to {}", path.display());
+            return Err(MyError::PermissionError(msg).into());
+        }
+        let result = if path.is_dir() {
+            fs::remove_dir(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|e| io_error(&path, e))?;
+        Ok(Type::Nil)
+    });
+}
+
+// the path argument, refused unless it resolves inside the scope.
+fn checked_path(
+    name: &str,
+    arguments: &[Type],
+    scope: &[PathBuf],
+    access: &str,
+) -> Result<PathBuf> {
+    let path = PathBuf::from(string_arg(name, arguments, 0)?);
+    if scope.is_empty() {
+        return Ok(path);
+    }
+
+    // the target may not exist yet, so resolve through its parent.
+    let resolved = match path.canonicalize() {
+        Ok(resolved) => Some(resolved),
+        Err(_) => match (path.parent(), path.file_name()) {
+            (Some(parent), Some(file_name)) => {
+                let parent = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+                parent.canonicalize().ok().map(|p| p.join(file_name))
+            }
+            _ => None,
+        },
+    };
+    match resolved {
+        Some(resolved) if scope.iter().any(|dir| resolved.starts_with(dir)) => Ok(resolved),
+        _ => {
+            let msg = format!("{name}(): no {access} access to {}", path.display());
+            Err(MyError::PermissionError(msg).into())
+        }
+    }
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> anyhow::Error {
+    MyError::IoError(format!("{}: {}", path.display(), err)).into()
+}
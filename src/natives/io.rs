@@ -0,0 +1,43 @@
+use super::string_arg;
+use crate::errors::MyError;
+use crate::interpreter::Interpreter;
+use crate::tokens::Type;
+
+// reading standard input, for scripts used as pipeline filters.
+pub fn define(interpreter: &Interpreter) {
+    // the next line without its terminator, nil at end of input.
+    interpreter.register_native("readLine", 0, |interpreter, _| {
+        let mut line = interpreter.read_line().map_err(io_error)?;
+        if line.is_empty() {
+            return Ok(Type::Nil);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Type::String(line))
+    });
+
+    // everything left, "" at end of input.
+    interpreter.register_native("readAll", 0, |interpreter, _| {
+        Ok(Type::String(interpreter.read_all().map_err(io_error)?))
+    });
+
+    // nil when the text is not a number, so input can be validated without try.
+    interpreter.register_native("parseNumber", 1, |_, arguments| {
+        let text = string_arg("parseNumber", arguments, 0)?.trim();
+        let numeric = text
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'));
+        match text.parse::<f64>() {
+            Ok(value) if numeric => Ok(Type::Number(value)),
+            _ => Ok(Type::Nil),
+        }
+    });
+}
+
+fn io_error(err: std::io::Error) -> anyhow::Error {
+    MyError::IoError(format!("stdin: {}", err)).into()
+}
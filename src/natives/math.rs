@@ -0,0 +1,113 @@
+use super::number_arg;
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::module::Module;
+use crate::stmt::NativeFunction;
+use crate::tokens::Type;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the `math` namespace: `math.sqrt(2)`, `math.PI`.
+pub fn define(interpreter: &Interpreter) {
+    let environment = Environment::new(None);
+    let native = |name: &str, arity, function: fn(&[Type]) -> anyhow::Result<Type>| {
+        let value = NativeFunction::new(name, arity, move |_, arguments| function(arguments));
+        environment.define(name, &Type::Native(value));
+    };
+
+    native("floor", 1, |a| {
+        Ok(Type::Number(number_arg("floor", a, 0)?.floor()))
+    });
+    native("ceil", 1, |a| {
+        Ok(Type::Number(number_arg("ceil", a, 0)?.ceil()))
+    });
+    // halves round away from zero.
+    native("round", 1, |a| {
+        Ok(Type::Number(number_arg("round", a, 0)?.round()))
+    });
+    native("abs", 1, |a| {
+        Ok(Type::Number(number_arg("abs", a, 0)?.abs()))
+    });
+    native("sqrt", 1, |a| {
+        Ok(Type::Number(number_arg("sqrt", a, 0)?.sqrt()))
+    });
+    native("exp", 1, |a| {
+        Ok(Type::Number(number_arg("exp", a, 0)?.exp()))
+    });
+    // natural logarithm.
+    native("log", 1, |a| {
+        Ok(Type::Number(number_arg("log", a, 0)?.ln()))
+    });
+    native("sin", 1, |a| {
+        Ok(Type::Number(number_arg("sin", a, 0)?.sin()))
+    });
+    native("cos", 1, |a| {
+        Ok(Type::Number(number_arg("cos", a, 0)?.cos()))
+    });
+    native("tan", 1, |a| {
+        Ok(Type::Number(number_arg("tan", a, 0)?.tan()))
+    });
+    native("asin", 1, |a| {
+        Ok(Type::Number(number_arg("asin", a, 0)?.asin()))
+    });
+    native("acos", 1, |a| {
+        Ok(Type::Number(number_arg("acos", a, 0)?.acos()))
+    });
+    native("atan", 1, |a| {
+        Ok(Type::Number(number_arg("atan", a, 0)?.atan()))
+    });
+    native("atan2", 2, |a| {
+        let y = number_arg("atan2", a, 0)?;
+        Ok(Type::Number(y.atan2(number_arg("atan2", a, 1)?)))
+    });
+    native("pow", 2, |a| {
+        let base = number_arg("pow", a, 0)?;
+        Ok(Type::Number(base.powf(number_arg("pow", a, 1)?)))
+    });
+    native("min", 2, |a| {
+        let lnum = number_arg("min", a, 0)?;
+        Ok(Type::Number(lnum.min(number_arg("min", a, 1)?)))
+    });
+    native("max", 2, |a| {
+        let lnum = number_arg("max", a, 0)?;
+        Ok(Type::Number(lnum.max(number_arg("max", a, 1)?)))
+    });
+
+    environment.define("PI", &Type::Number(std::f64::consts::PI));
+    environment.define("E", &Type::Number(std::f64::consts::E));
+
+    // splitmix64, seeded from the clock unless a script calls `math.seed(n)`.
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    let state = Rc::new(Cell::new(seed));
+
+    let seed_state = state.clone();
+    let seed = NativeFunction::new("seed", 1, move |_, arguments| {
+        seed_state.set(number_arg("seed", arguments, 0)?.to_bits());
+        Ok(Type::Nil)
+    });
+    environment.define("seed", &Type::Native(seed));
+
+    // uniform in [0, 1).
+    let random = NativeFunction::new("random", 0, move |_, _| {
+        let next = state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        state.set(next);
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Ok(Type::Number((z >> 11) as f64 / (1u64 << 53) as f64))
+    });
+    environment.define("random", &Type::Native(random));
+
+    let module = Module {
+        name: "math".to_string(),
+        path: PathBuf::new(),
+        environment: Rc::new(RefCell::new(environment)),
+    };
+    interpreter.define_builtin("math", &Type::Module(Rc::new(module)));
+}
@@ -0,0 +1,138 @@
+use super::{index_arg, number_arg, string_arg};
+use crate::errors::MyError;
+use crate::interpreter::Interpreter;
+use crate::tokens::Type;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// string builtins. positions and lengths count chars, not bytes.
+pub fn define(interpreter: &Interpreter) {
+    interpreter.register_native("len", 1, |_, arguments| match &arguments[0] {
+        Type::String(s) => Ok(Type::Number(s.chars().count() as f64)),
+        Type::List(items) => Ok(Type::Number(items.borrow().len() as f64)),
+        _ => Err(MyError::CastError("len(): argument 1 must be a string or list".into()).into()),
+    });
+
+    // end is exclusive.
+    interpreter.register_native("substring", 3, |_, arguments| {
+        let s = string_arg("substring", arguments, 0)?;
+        let start = index_arg("substring", arguments, 1)?;
+        let end = index_arg("substring", arguments, 2)?;
+        let length = s.chars().count();
+        if start > end || end > length {
+            let msg =
+                format!("substring(): range {start}..{end} out of bounds for length {length}");
+            return Err(MyError::IndexError(msg).into());
+        }
+        Ok(Type::String(
+            s.chars().skip(start).take(end - start).collect(),
+        ))
+    });
+
+    // -1 when not found.
+    interpreter.register_native("indexOf", 2, |_, arguments| {
+        let s = string_arg("indexOf", arguments, 0)?;
+        let needle = string_arg("indexOf", arguments, 1)?;
+        let index = match s.find(needle) {
+            Some(byte) => s[..byte].chars().count() as f64,
+            None => -1.0,
+        };
+        Ok(Type::Number(index))
+    });
+
+    // an empty separator splits into chars.
+    interpreter.register_native("split", 2, |_, arguments| {
+        let s = string_arg("split", arguments, 0)?;
+        let separator = string_arg("split", arguments, 1)?;
+        let parts: Vec<Type> = if separator.is_empty() {
+            s.chars().map(|c| Type::String(c.to_string())).collect()
+        } else {
+            s.split(separator)
+                .map(|part| Type::String(part.to_string()))
+                .collect()
+        };
+        Ok(Type::List(Rc::new(RefCell::new(parts))))
+    });
+
+    interpreter.register_native("join", 2, |_, arguments| {
+        let separator = string_arg("join", arguments, 1)?;
+        match &arguments[0] {
+            Type::List(items) => {
+                let parts: Vec<String> = items.borrow().iter().map(|v| v.to_string()).collect();
+                Ok(Type::String(parts.join(separator)))
+            }
+            _ => Err(MyError::CastError("join(): argument 1 must be a list".into()).into()),
+        }
+    });
+
+    interpreter.register_native("trim", 1, |_, arguments| {
+        Ok(Type::String(
+            string_arg("trim", arguments, 0)?.trim().to_string(),
+        ))
+    });
+
+    interpreter.register_native("upper", 1, |_, arguments| {
+        Ok(Type::String(
+            string_arg("upper", arguments, 0)?.to_uppercase(),
+        ))
+    });
+
+    interpreter.register_native("lower", 1, |_, arguments| {
+        Ok(Type::String(
+            string_arg("lower", arguments, 0)?.to_lowercase(),
+        ))
+    });
+
+    // replaces every occurrence.
+    interpreter.register_native("replace", 3, |_, arguments| {
+        let s = string_arg("replace", arguments, 0)?;
+        let from = string_arg("replace", arguments, 1)?;
+        let to = string_arg("replace", arguments, 2)?;
+        if from.is_empty() {
+            let msg = "replace(): argument 2 must not be empty".to_string();
+            return Err(MyError::CastError(msg).into());
+        }
+        Ok(Type::String(s.replace(from, to)))
+    });
+
+    interpreter.register_native("startsWith", 2, |_, arguments| {
+        let s = string_arg("startsWith", arguments, 0)?;
+        let prefix = string_arg("startsWith", arguments, 1)?;
+        Ok(Type::Bool(s.starts_with(prefix)))
+    });
+
+    interpreter.register_native("endsWith", 2, |_, arguments| {
+        let s = string_arg("endsWith", arguments, 0)?;
+        let suffix = string_arg("endsWith", arguments, 1)?;
+        Ok(Type::Bool(s.ends_with(suffix)))
+    });
+
+    // unicode code point of a single-char string.
+    interpreter.register_native("ord", 1, |_, arguments| {
+        let s = string_arg("ord", arguments, 0)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Type::Number(c as u32 as f64)),
+            _ => {
+                let msg = "ord(): argument 1 must be a single character".to_string();
+                Err(MyError::CastError(msg).into())
+            }
+        }
+    });
+
+    interpreter.register_native("chr", 1, |_, arguments| {
+        let code = number_arg("chr", arguments, 0)?;
+        let c = if code >= 0.0 && code.fract() == 0.0 && code <= u32::MAX as f64 {
+            char::from_u32(code as u32)
+        } else {
+            None
+        };
+        match c {
+            Some(c) => Ok(Type::String(c.to_string())),
+            None => {
+                let msg = format!("chr(): {} is not a valid code point", Type::Number(code));
+                Err(MyError::CastError(msg).into())
+            }
+        }
+    });
+}
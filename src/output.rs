@@ -0,0 +1,34 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+// in-memory sink for capturing what a script prints.
+// clones share the same buffer, so keep one and hand the other to the interpreter.
+#[derive(Debug, Clone, Default)]
+pub struct SharedBuffer {
+    buf: Rc<RefCell<Vec<u8>>>,
+}
+
+impl SharedBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buf.borrow()).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.buf.borrow_mut().clear();
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.borrow_mut().write(data)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
-use crate::errors::MyError;
+use crate::errors::{MyError, SyntaxError};
 use crate::expr::Expr;
-use crate::stmt::Stmt;
+use crate::pattern::Pattern;
+use crate::stmt::{CatchClause, MatchArm, Stmt};
 use crate::tokens::{Token, TokenType, Type};
 use anyhow::Result;
 use log::debug;
 use std::cell::RefCell;
+use std::path::Path;
 
 const MAX_ARG_NUM: usize = 255;
 
@@ -22,6 +24,22 @@ statement      → exprStmt
                | ifStmt
                | whileStmt
                | forStmt
+               | matchStmt
+               | returnStmt
+               | throwStmt
+               | tryStmt
+               | importStmt
+importStmt     → "import" ( STRING | IDENTIFIER ) ";" ;
+returnStmt     → "return" expression? ";" ;
+throwStmt      → "throw" expression ";" ;
+tryStmt        → "try" block ( "catch" "(" IDENTIFIER ")" block )? ( "finally" block )? ;
+matchStmt      → "match" "(" expression ")" "{" matchArm* "}" ;
+matchArm       → pattern ( "if" expression )? "=>" statement ","? ;
+pattern        → subpattern ( "|" subpattern )* ;
+subpattern     → "_" | IDENTIFIER
+               | literal ( ( ".." | "..=" ) literal )?
+               | "[" ( pattern ( "," pattern )* ( "," ".." IDENTIFIER? )? )? "]" ;
+literal        → "-"? NUMBER | STRING | "true" | "false" | "nil" ;
 whileStmt      → "while" "(" expression ")" statement ;
 ifStmt         → "if" "(" expression ")" statement
 forStmt        → "for" "(" ( varDecl | exprStmt | ";" )
@@ -33,19 +51,29 @@ block          → "{" declaration* "}" ;
 exprStmt       → expression ";" ;
 printStmt      → "print" expression ";" ;
 expression     → assignment ;
-assignment     → IDENTIFIER "=" assignment
-               | logic_or;
+assignment     → IDENTIFIER ( "=" | "+=" | "-=" | "*=" | "/=" | "%=" ) assignment
+               | conditional;
+conditional    → logic_or ( "?" expression ":" conditional )? ;
 logic_or       → logic_and ( "or" logic_and )* ;
 logic_and      → equality ( "and" equality )* ;
-equality       → comparison ( ( "!=" | "==" ) comparison )* ;
-comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
+equality       → bit_or ( ( "!=" | "==" ) bit_or )* ;
+bit_or         → bit_xor ( "|" bit_xor )* ;
+bit_xor        → bit_and ( "^" bit_and )* ;
+bit_and        → comparison ( "&" comparison )* ;
+comparison     → shift ( ( ">" | ">=" | "<" | "<=" ) shift )* ;
+shift          → term ( ( "<<" | ">>" ) term )* ;
 term           → factor ( ( "-" | "+" ) factor )* ;
-factor         → unary ( ( "/" | "*" ) unary )* ;
-unary          → ( "!" | "-" ) unary | call ;
-call           → primary ( "(" arguments? ")" )* ;
+factor         → unary ( ( "/" | "*" | "%" ) unary )* ;
+unary          → ( "!" | "-" | "~" ) unary
+               | ( "++" | "--" ) unary
+               | power ;
+power          → postfix ( "**" unary )? ;
+postfix        → call ( "++" | "--" )? ;
+call           → primary ( "(" arguments? ")" | "." IDENTIFIER )* ;
 arguments      → expression ( "," expression )* ;
 primary        → NUMBER | STRING | "true" | "false" | "nil"
                | "(" expression ")" ;
+               | "[" arguments? "]" ;
                | IDENTIFIER
  */
 pub struct Parser {
@@ -70,18 +98,27 @@ impl Parser {
         Ok(statements)
     }
 
+    // a single expression, optionally followed by ';'.
+    pub fn parse_expression(&self) -> Result<Expr> {
+        let expr = self.expression()?;
+        self.is_match(&[TokenType::SEMICOLON]);
+        if !self.is_end() {
+            return Err(self.error(self.peek(0), "Expect end of expression."));
+        }
+
+        Ok(expr)
+    }
+
     fn declaration(&self) -> Result<Stmt> {
         if self.is_match(&[TokenType::FUN]) {
-            return self.function("function");
+            self.function("function")
         } else if self.is_match(&[TokenType::VAR]) {
             match self.var_declaration() {
                 Err(e) => {
                     //self.synchronize();
-                    return Err(e);
-                }
-                Ok(v) => {
-                    return Ok(v);
+                    Err(e)
                 }
+                Ok(v) => Ok(v),
             }
         } else {
             self.statement()
@@ -102,7 +139,7 @@ impl Parser {
         if !self.check(&TokenType::RightParen) {
             loop {
                 if params.len() >= MAX_ARG_NUM {
-                    return Err(MyError::MaxArgumentNumError.into());
+                    return Err(self.error(self.peek(0), "Can't have more than 255 parameters."));
                 }
 
                 params.push(
@@ -122,10 +159,7 @@ impl Parser {
             &format!("{}{}{}", "Expect '{' before ", kind, " body."),
         )?;
 
-        let body = match self.block_stmt()? {
-            Stmt::Block(v) => v,
-            _ => panic!("should not be here."),
-        };
+        let body = self.block_body()?;
 
         Ok(Stmt::Function {
             name: name.clone(),
@@ -165,15 +199,241 @@ impl Parser {
             return self.block_stmt();
         } else if self.is_match(&[TokenType::BREAK]) {
             return self.break_stmt();
+        } else if self.is_match(&[TokenType::MATCH]) {
+            return self.match_stmt();
+        } else if self.is_match(&[TokenType::RETURN]) {
+            return self.return_stmt();
+        } else if self.is_match(&[TokenType::THROW]) {
+            return self.throw_stmt();
+        } else if self.is_match(&[TokenType::TRY]) {
+            return self.try_stmt();
+        } else if self.is_match(&[TokenType::IMPORT]) {
+            return self.import_stmt();
         }
 
         self.expr_stmt()
     }
 
+    fn return_stmt(&self) -> Result<Stmt> {
+        let keyword = self.previous().clone();
+        let value = if self.check(&TokenType::SEMICOLON) {
+            Expr::Null
+        } else {
+            self.expression()?
+        };
+        self.consume(TokenType::SEMICOLON, "Expect ';' after return value.")?;
+
+        Ok(Stmt::Return { keyword, value })
+    }
+
+    fn throw_stmt(&self) -> Result<Stmt> {
+        let keyword = self.previous().clone();
+        let value = self.expression()?;
+        self.consume(TokenType::SEMICOLON, "Expect ';' after thrown value.")?;
+
+        Ok(Stmt::Throw { keyword, value })
+    }
+
+    fn try_stmt(&self) -> Result<Stmt> {
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block_body()?;
+
+        let catch_clause = if self.is_match(&[TokenType::CATCH]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self
+                .consume(TokenType::IDENTIFIER, "Expect error variable name.")?
+                .clone();
+            self.consume(TokenType::RightParen, "Expect ')' after error variable.")?;
+            self.consume(TokenType::LeftBrace, "Expect '{' before catch body.")?;
+            Some(CatchClause {
+                name,
+                body: self.block_body()?,
+            })
+        } else {
+            None
+        };
+
+        let finally_body = if self.is_match(&[TokenType::FINALLY]) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block_body()?)
+        } else {
+            None
+        };
+
+        if catch_clause.is_none() && finally_body.is_none() {
+            return Err(self.error(self.peek(0), "Expect 'catch' or 'finally' after try block."));
+        }
+
+        Ok(Stmt::Try {
+            body,
+            catch_clause,
+            finally_body,
+        })
+    }
+
+    // `import "lib/util.lox";` and `import util;` both bind `util`.
+    fn import_stmt(&self) -> Result<Stmt> {
+        let keyword = self.previous().clone();
+        let (path, name) = if self.is_match(&[TokenType::STRING]) {
+            let path = self.previous().literal.to_string();
+            let name = Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            (path, name)
+        } else {
+            let name = self
+                .consume(
+                    TokenType::IDENTIFIER,
+                    "Expect module name or path after 'import'.",
+                )?
+                .lexeme
+                .clone();
+            (format!("{}.lox", name), name)
+        };
+        self.consume(TokenType::SEMICOLON, "Expect ';' after import.")?;
+
+        Ok(Stmt::Import {
+            keyword,
+            path,
+            name,
+        })
+    }
+
+    fn block_body(&self) -> Result<Vec<Stmt>> {
+        match self.block_stmt()? {
+            Stmt::Block(v) => Ok(v),
+            _ => panic!("should not be here."),
+        }
+    }
+
+    fn match_stmt(&self) -> Result<Stmt> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'match'.")?;
+        let value = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after match value.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before match arms.")?;
+
+        let mut arms = Vec::new();
+        while !self.is_end() && !self.check(&TokenType::RightBrace) {
+            let pattern = self.pattern()?;
+            let guard = if self.is_match(&[TokenType::IF]) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(TokenType::FatArrow, "Expect '=>' after match pattern.")?;
+            let body = self.statement()?;
+            self.is_match(&[TokenType::COMMA]);
+
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+
+        Ok(Stmt::Match {
+            keyword,
+            value,
+            arms,
+        })
+    }
+
+    fn pattern(&self) -> Result<Pattern> {
+        let mut alternatives = vec![self.subpattern()?];
+        while self.is_match(&[TokenType::PIPE]) {
+            alternatives.push(self.subpattern()?);
+        }
+
+        if alternatives.len() == 1 {
+            return Ok(alternatives.remove(0));
+        }
+        Ok(Pattern::Alternative(alternatives))
+    }
+
+    fn subpattern(&self) -> Result<Pattern> {
+        if self.is_match(&[TokenType::IDENTIFIER]) {
+            let name = self.previous();
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            return Ok(Pattern::Binding(name.clone()));
+        }
+
+        if self.is_match(&[TokenType::LeftBracket]) {
+            return self.list_pattern();
+        }
+
+        let literal = self.pattern_literal()?;
+        if self.is_match(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let inclusive = self.previous().token_type == TokenType::DotDotEqual;
+            let end = self.pattern_literal()?;
+            return match (literal, end) {
+                (Type::Number(start), Type::Number(end)) => Ok(Pattern::Range {
+                    start,
+                    end,
+                    inclusive,
+                }),
+                _ => Err(self.error(self.previous(), "Range bounds must be numbers.")),
+            };
+        }
+
+        Ok(Pattern::Literal(literal))
+    }
+
+    fn list_pattern(&self) -> Result<Pattern> {
+        let mut elements = Vec::new();
+        let mut rest = None;
+
+        while !self.check(&TokenType::RightBracket) {
+            if self.is_match(&[TokenType::DotDot]) {
+                rest = Some(Box::new(
+                    if self.is_match(&[TokenType::IDENTIFIER]) && self.previous().lexeme != "_" {
+                        Pattern::Binding(self.previous().clone())
+                    } else {
+                        Pattern::Wildcard
+                    },
+                ));
+                break;
+            }
+
+            elements.push(self.pattern()?);
+            if !self.is_match(&[TokenType::COMMA]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBracket, "Expect ']' after list pattern.")?;
+
+        Ok(Pattern::List { elements, rest })
+    }
+
+    fn pattern_literal(&self) -> Result<Type> {
+        let token = self.peek(0);
+        self.advance();
+        match token.token_type {
+            TokenType::FALSE => Ok(Type::Bool(false)),
+            TokenType::TRUE => Ok(Type::Bool(true)),
+            TokenType::NIL => Ok(Type::Nil),
+            TokenType::NUMBER | TokenType::STRING => Ok(token.literal.clone()),
+            TokenType::MINUS if self.check(&TokenType::NUMBER) => {
+                let number = self.peek(0);
+                self.advance();
+                match number.literal {
+                    Type::Number(n) => Ok(Type::Number(-n)),
+                    _ => Err(self.error(number, "Expected number.")),
+                }
+            }
+            _ => Err(self.error(token, "Expected pattern.")),
+        }
+    }
+
     fn break_stmt(&self) -> Result<Stmt> {
+        let keyword = self.previous().clone();
         self.consume(TokenType::SEMICOLON, "Expect ';' after break.")?;
 
-        Ok(Stmt::Break)
+        Ok(Stmt::Break { keyword })
     }
 
     fn for_stmt(&self) -> Result<Stmt> {
@@ -295,7 +555,7 @@ impl Parser {
     }
 
     fn assignment(&self) -> Result<Expr> {
-        let expr = self.or()?;
+        let expr = self.conditional()?;
         if self.is_match(&[TokenType::EQUAL]) {
             let equals = self.previous();
             let value = self.assignment()?;
@@ -307,11 +567,48 @@ impl Parser {
                     value: Box::new(value),
                 });
             } else {
-                return Err(MyError::InvalidAssignmentTargetError(equals.lexeme.clone()).into());
+                return Err(Self::invalid_target(equals));
+            }
+        } else if self.is_match(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let op = self.previous().clone();
+            let value = self.assignment()?;
+
+            if !Self::is_assignable(&expr) {
+                return Err(Self::invalid_target(&op));
             }
+            return Ok(Expr::CompoundAssign {
+                target: Box::new(expr),
+                op,
+                value: Box::new(value),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    fn conditional(&self) -> Result<Expr> {
+        let condition = self.or()?;
+        if self.is_match(&[TokenType::QUESTION]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::COLON,
+                "Expect ':' after then branch of conditional.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            });
         }
 
-        return Ok(expr);
+        Ok(condition)
     }
 
     fn or(&self) -> Result<Expr> {
@@ -347,10 +644,19 @@ impl Parser {
     }
 
     fn equality(&self) -> Result<Expr> {
-        self.binary_builder(
-            &[TokenType::BangEqual, TokenType::EqualEqual],
-            Self::comparsion,
-        )
+        self.binary_builder(&[TokenType::BangEqual, TokenType::EqualEqual], Self::bit_or)
+    }
+
+    fn bit_or(&self) -> Result<Expr> {
+        self.binary_builder(&[TokenType::PIPE], Self::bit_xor)
+    }
+
+    fn bit_xor(&self) -> Result<Expr> {
+        self.binary_builder(&[TokenType::CARET], Self::bit_and)
+    }
+
+    fn bit_and(&self) -> Result<Expr> {
+        self.binary_builder(&[TokenType::AMPERSAND], Self::comparsion)
     }
 
     fn comparsion(&self) -> Result<Expr> {
@@ -361,6 +667,13 @@ impl Parser {
                 TokenType::LESS,
                 TokenType::LessEqual,
             ],
+            Self::shift,
+        )
+    }
+
+    fn shift(&self) -> Result<Expr> {
+        self.binary_builder(
+            &[TokenType::LessLess, TokenType::GreaterGreater],
             Self::term,
         )
     }
@@ -370,11 +683,14 @@ impl Parser {
     }
 
     fn factor(&self) -> Result<Expr> {
-        self.binary_builder(&[TokenType::SLASH, TokenType::STAR], Self::unary)
+        self.binary_builder(
+            &[TokenType::SLASH, TokenType::STAR, TokenType::PERCENT],
+            Self::unary,
+        )
     }
 
     fn unary(&self) -> Result<Expr> {
-        if self.is_match(&[TokenType::BANG, TokenType::MINUS]) {
+        if self.is_match(&[TokenType::BANG, TokenType::MINUS, TokenType::TILDE]) {
             let operator = self.previous();
             let right = self.unary()?;
             return Ok(Expr::Unary {
@@ -383,7 +699,62 @@ impl Parser {
             });
         }
 
-        self.call()
+        // the operand is a single call or variable, so `++a ** 2` is `(++a) ** 2`.
+        if self.is_match(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let op = self.previous().clone();
+            let target = self.call()?;
+            let expr = self.increment(target, op, true)?;
+            return self.exponent(expr);
+        }
+
+        self.power()
+    }
+
+    fn postfix(&self) -> Result<Expr> {
+        let expr = self.call()?;
+        if self.is_match(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let op = self.previous().clone();
+            return self.increment(expr, op, false);
+        }
+
+        Ok(expr)
+    }
+
+    fn increment(&self, target: Expr, op: Token, prefix: bool) -> Result<Expr> {
+        if !Self::is_assignable(&target) {
+            return Err(Self::invalid_target(&op));
+        }
+
+        Ok(Expr::Increment {
+            target: Box::new(target),
+            op,
+            prefix,
+        })
+    }
+
+    // targets of compound assignment and ++/--.
+    fn is_assignable(expr: &Expr) -> bool {
+        matches!(expr, Expr::Var(_))
+    }
+
+    // right-associative and binds tighter than a leading "-": -2 ** 2 == -4.
+    fn power(&self) -> Result<Expr> {
+        let expr = self.postfix()?;
+        self.exponent(expr)
+    }
+
+    fn exponent(&self, base: Expr) -> Result<Expr> {
+        if self.is_match(&[TokenType::StarStar]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(base),
+                op: operator.clone(),
+                right: Box::new(right),
+            });
+        }
+
+        Ok(base)
     }
 
     fn call(&self) -> Result<Expr> {
@@ -392,6 +763,13 @@ impl Parser {
         loop {
             if self.is_match(&[TokenType::LeftParen]) {
                 expr = self.finish_call(&expr)?
+            } else if self.is_match(&[TokenType::DOT]) {
+                let name =
+                    self.consume(TokenType::IDENTIFIER, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name: name.clone(),
+                };
             } else {
                 break;
             }
@@ -406,7 +784,7 @@ impl Parser {
             //have arguments.
             loop {
                 if arguments.len() >= MAX_ARG_NUM {
-                    return Err(MyError::MaxArgumentNumError.into());
+                    return Err(self.error(self.peek(0), "Can't have more than 255 arguments."));
                 }
                 arguments.push(self.expression()?);
                 if !self.is_match(&[TokenType::COMMA]) {
@@ -440,10 +818,20 @@ impl Parser {
                 Ok(Expr::Grouping(Box::new(expr)))
             }
             TokenType::IDENTIFIER => Ok(Expr::Var(self.previous().clone())),
-            _ => {
-                self.error(self.peek(0), "Expected expression.");
-                Err(MyError::NotImplementedError.into())
+            TokenType::LeftBracket => {
+                let mut elements = Vec::new();
+                if !self.check(&TokenType::RightBracket) {
+                    loop {
+                        elements.push(self.expression()?);
+                        if !self.is_match(&[TokenType::COMMA]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+                Ok(Expr::List(elements))
             }
+            _ => Err(self.error(operator, "Expected expression.")),
         }
     }
 
@@ -454,7 +842,7 @@ impl Parser {
             return Ok(current);
         }
 
-        Err(MyError::ParseError(msg.into()).into())
+        Err(self.error(self.peek(0), msg))
     }
 
     fn binary_builder<F>(&self, t: &[TokenType], op_method: F) -> Result<Expr>
@@ -505,7 +893,7 @@ impl Parser {
             return false;
         }
 
-        return self.peek(0).token_type == *t;
+        self.peek(0).token_type == *t
     }
 
     fn is_end(&self) -> bool {
@@ -517,17 +905,16 @@ impl Parser {
         self.tokens.get(nth).unwrap()
     }
 
-    fn error(&self, t: &Token, msg: &str) {
-        match t.token_type {
-            TokenType::EOF => self.report(t.line, "at end", msg),
-            _ => self.report(t.line, &format!("{}{}", " at ", t.lexeme), msg),
-        }
+    fn error(&self, t: &Token, msg: &str) -> anyhow::Error {
+        MyError::ParseError(SyntaxError::at(t, msg)).into()
     }
 
-    fn report(&self, line: usize, info: &str, msg: &str) {
-        println!("[line {}] Error {}: {}", line, info, msg);
+    fn invalid_target(op: &Token) -> anyhow::Error {
+        MyError::InvalidAssignmentTargetError(SyntaxError::at(op, "Invalid assignment target."))
+            .into()
     }
 
+    #[allow(dead_code)]
     fn synchronize(&self) {
         self.advance();
 
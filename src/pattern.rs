@@ -0,0 +1,24 @@
+use crate::tokens::{Token, Type};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    // `1`, `"a"`, `nil`
+    Literal(Type),
+    // `1..5`, `1..=5`
+    Range {
+        start: f64,
+        end: f64,
+        inclusive: bool,
+    },
+    // `_`
+    Wildcard,
+    // `x`, binds the matched value.
+    Binding(Token),
+    // `1 | 2 | 3`
+    Alternative(Vec<Pattern>),
+    // `[a, b]`, `[first, ..rest]`; `rest` is a wildcard or binding for the tail.
+    List {
+        elements: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+}
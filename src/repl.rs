@@ -0,0 +1,336 @@
+use anyhow::Result;
+use myjlox::errors::exit_code;
+use myjlox::parser::Parser;
+use myjlox::scanner::{completions, is_incomplete, Scanner};
+use myjlox::stmt::Stmt;
+use myjlox::{color, dump, Interpreter, MyError};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::history::FileHistory;
+use rustyline::{Config, Context, Editor, Helper, Hinter, Validator};
+use std::borrow::Cow;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const PROMPT: &str = "> ";
+// shown while a statement spans several lines.
+const CONTINUATION: &str = ". ";
+const HISTORY_FILE: &str = ".myjlox_history";
+const HISTORY_SIZE: usize = 1000;
+const HELP: &str = "\
+:env           list the variables in scope with their values
+:load FILE     run a script in this session
+:reset         start over with a fresh interpreter
+:ast CODE      print the syntax tree of CODE
+:tokens CODE   print the tokens of CODE
+:time CODE     run CODE and print how long it took
+:save FILE     write the inputs that ran without error to FILE
+:help          show this list
+";
+
+// reads statements until end of input. a statement may span lines: input is collected
+// while it is incomplete, and an empty line runs whatever has been typed so far.
+// lines starting with ':' are commands to the REPL itself, see HELP.
+// `fresh` gives the interpreter `:reset` switches to. the session ends with the exit
+// status of the last input that failed, as MyError::Exit, having reported it already.
+pub fn run(interpreter: &Interpreter, fresh: &dyn Fn() -> Result<Interpreter>) -> Result<()> {
+    let colors = color::enabled();
+    if colors {
+        interpreter.set_echo(color::value);
+    }
+    let mut lines = Lines::new(colors)?;
+    let mut session = Session {
+        initial: interpreter,
+        reset: None,
+        fresh,
+        inputs: Vec::new(),
+        colors,
+        status: 0,
+    };
+    let result = session.run(&mut lines);
+    lines.save_history();
+
+    result
+}
+
+struct Session<'a> {
+    initial: &'a Interpreter,
+    // replaces `initial` after `:reset`.
+    reset: Option<Interpreter>,
+    fresh: &'a dyn Fn() -> Result<Interpreter>,
+    // inputs that ran without error, for `:save`.
+    inputs: Vec<String>,
+    colors: bool,
+    // exit status for the last failed input, 0 while nothing has failed.
+    status: i32,
+}
+
+impl Session<'_> {
+    fn interpreter(&self) -> &Interpreter {
+        self.reset.as_ref().unwrap_or(self.initial)
+    }
+
+    fn run(&mut self, lines: &mut Lines) -> Result<()> {
+        let mut source = String::new();
+        loop {
+            if source.is_empty() {
+                lines.set_names(self.interpreter().names());
+            }
+            let prompt = if source.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION
+            };
+            let line = match lines.read(prompt)? {
+                Line::Text(line) => line,
+                // ctrl-c drops the statement being typed.
+                Line::Interrupted => {
+                    source.clear();
+                    continue;
+                }
+                Line::Eof => break,
+            };
+
+            let result = if source.is_empty() && line.trim_start().starts_with(':') {
+                lines.add_history(&line)?;
+                self.command(line.trim())
+            } else {
+                let blank = line.trim().is_empty();
+                source.push_str(&line);
+                if !blank && is_incomplete(&source) {
+                    continue;
+                }
+                let source = std::mem::take(&mut source);
+                lines.add_history(&source)?;
+                self.execute(&source)
+            };
+
+            if let Err(e) = result {
+                // the session survives script errors, but not exit().
+                if matches!(e.downcast_ref::<MyError>(), Some(MyError::Exit(_))) {
+                    return Err(e);
+                }
+                self.interpreter().report_error(&e);
+                self.status = exit_code(&e);
+            }
+        }
+
+        match self.status {
+            0 => Ok(()),
+            status => Err(MyError::Exit(status).into()),
+        }
+    }
+
+    fn execute(&mut self, source: &str) -> Result<()> {
+        self.interpreter().run(source)?;
+        self.inputs.push(source.to_string());
+
+        Ok(())
+    }
+
+    fn command(&mut self, line: &str) -> Result<()> {
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        let needs_argument = matches!(name, ":load" | ":ast" | ":tokens" | ":time" | ":save");
+        if needs_argument && argument.is_empty() {
+            let message = format!("{} needs an argument, see :help.", name);
+            self.interpreter()
+                .report_warning("MissingArgument", &message);
+            return Ok(());
+        }
+
+        match name {
+            ":help" => print!("{}", HELP),
+            ":env" => {
+                for (name, value) in self.interpreter().bindings() {
+                    match value {
+                        Some(value) => println!("{} = {}", name, value.repr()),
+                        None => println!("{} (uninitialized)", name),
+                    }
+                }
+            }
+            ":load" => {
+                self.interpreter().run_file(Path::new(argument))?;
+                // saved as what ran, so the saved file doesn't depend on this one.
+                let mut source = fs::read_to_string(argument)
+                    .map_err(|e| MyError::IoError(format!("{}: {}", argument, e)))?;
+                if !source.ends_with('\n') {
+                    source.push('\n');
+                }
+                self.inputs.push(source);
+            }
+            ":reset" => {
+                let interpreter = (self.fresh)()?;
+                if self.colors {
+                    interpreter.set_echo(color::value);
+                }
+                self.reset = Some(interpreter);
+                self.inputs.clear();
+            }
+            ":ast" => print!("{}", ast(argument)?),
+            ":tokens" => print!(
+                "{}",
+                dump::tokens_text(&Scanner::new(argument).scan_tokens()?)
+            ),
+            ":time" => {
+                let start = Instant::now();
+                let result = self.execute(&format!("{}\n", argument));
+                println!("time: {:?}", start.elapsed());
+                result?;
+            }
+            ":save" => fs::write(argument, self.inputs.concat())
+                .map_err(|e| MyError::IoError(format!("{}: {}", argument, e)))?,
+            _ => {
+                let message = format!("Unknown command {}, see :help.", name);
+                self.interpreter()
+                    .report_warning("UnknownCommand", &message);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// statements as `myjlox ast` prints them, or a bare expression such as `1 + 2`.
+fn ast(source: &str) -> Result<String> {
+    let tokens = Scanner::new(source).scan_tokens()?;
+    let stmts = match Parser::new(&tokens).parse() {
+        Ok(stmts) => stmts,
+        Err(e) => match Parser::new(&tokens).parse_expression() {
+            Ok(expr) => vec![Stmt::ExprStmt(expr)],
+            Err(_) => return Err(e),
+        },
+    };
+
+    Ok(dump::ast_text(&stmts))
+}
+
+enum Line {
+    // with its line terminator.
+    Text(String),
+    Interrupted,
+    Eof,
+}
+
+// a line editor with history on a terminal, plain stdin otherwise.
+enum Lines {
+    Editor(Box<Editor<LoxHelper, FileHistory>>),
+    Plain,
+}
+
+impl Lines {
+    fn new(colors: bool) -> Result<Self> {
+        if !std::io::stdin().is_terminal() {
+            return Ok(Lines::Plain);
+        }
+
+        let config = Config::builder()
+            .max_history_size(HISTORY_SIZE)?
+            .auto_add_history(false)
+            .build();
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(LoxHelper {
+            names: Vec::new(),
+            colors,
+        }));
+        if let Some(path) = history_path() {
+            // there is none on the first run.
+            let _ = editor.load_history(&path);
+        }
+
+        Ok(Lines::Editor(Box::new(editor)))
+    }
+
+    fn read(&mut self, prompt: &str) -> Result<Line> {
+        match self {
+            Lines::Editor(editor) => match editor.readline(prompt) {
+                Ok(line) => Ok(Line::Text(line + "\n")),
+                Err(ReadlineError::Interrupted) => Ok(Line::Interrupted),
+                Err(ReadlineError::Eof) => Ok(Line::Eof),
+                Err(e) => Err(e.into()),
+            },
+            Lines::Plain => {
+                print!("{}", prompt);
+                std::io::stdout().flush()?;
+                let mut line = String::new();
+                match std::io::stdin().lock().read_line(&mut line)? {
+                    0 => Ok(Line::Eof),
+                    _ => Ok(Line::Text(line)),
+                }
+            }
+        }
+    }
+
+    // a command, or a statement with all of its lines, so it can be recalled as a whole.
+    fn add_history(&mut self, entry: &str) -> Result<()> {
+        let entry = entry.trim_end();
+        if let Lines::Editor(editor) = self {
+            if !entry.trim().is_empty() {
+                editor.add_history_entry(entry)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // names the completer offers besides keywords.
+    fn set_names(&mut self, names: Vec<String>) {
+        if let Lines::Editor(editor) = self {
+            if let Some(helper) = editor.helper_mut() {
+                helper.names = names;
+            }
+        }
+    }
+
+    fn save_history(&mut self) {
+        if let (Lines::Editor(editor), Some(path)) = (self, history_path()) {
+            if let Err(e) = editor.save_history(&path) {
+                log::warn!("could not save history to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    home::home_dir().map(|home| home.join(HISTORY_FILE))
+}
+
+#[derive(Helper, Hinter, Validator)]
+struct LoxHelper {
+    names: Vec<String>,
+    colors: bool,
+}
+
+// colors the line as it is typed, from its tokens.
+impl Highlighter for LoxHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if self.colors {
+            Cow::Owned(color::highlight(line))
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        self.colors
+    }
+}
+
+// completes the identifier before the cursor from keywords and defined names.
+impl Completer for LoxHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(completions(line, pos, &self.names))
+    }
+}
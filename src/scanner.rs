@@ -1,3 +1,4 @@
+use crate::errors::{MyError, Span, SyntaxError};
 use crate::tokens::{Token, TokenType, Type};
 use anyhow::Result;
 use std::cell::RefCell;
@@ -22,31 +23,82 @@ lazy_static! {
         ("var", TokenType::VAR),
         ("while", TokenType::WHILE),
         ("break", TokenType::BREAK),
+        ("match", TokenType::MATCH),
+        ("try", TokenType::TRY),
+        ("catch", TokenType::CATCH),
+        ("finally", TokenType::FINALLY),
+        ("throw", TokenType::THROW),
+        ("import", TokenType::IMPORT),
     ]);
 }
 
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    MAP.keys().copied()
+}
+
+pub fn is_keyword(token_type: TokenType) -> bool {
+    MAP.values().any(|keyword| *keyword == token_type)
+}
+
+// completions for the identifier ending at byte `pos` of `line`: where it starts, and the
+// keywords and `names` it is a prefix of, sorted. nothing is offered for an empty prefix.
+pub fn completions(line: &str, pos: usize, names: &[String]) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let prefix = &line[start..pos];
+    if prefix.is_empty() {
+        return (pos, Vec::new());
+    }
+
+    let mut candidates: Vec<String> = keywords()
+        .map(str::to_string)
+        .chain(names.iter().cloned())
+        .filter(|candidate| candidate.starts_with(prefix))
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+
+    (start, candidates)
+}
+
+const UNTERMINATED_STRING: &str = "Unterminated string.";
+
 pub struct Scanner {
-    source: String,
+    // indexed by char so that non-ASCII text in strings scans correctly.
+    source: Vec<char>,
     tokens: RefCell<Vec<Token>>,
     start: RefCell<usize>,
     current: RefCell<usize>,
     line: RefCell<usize>,
+    // index of the first char on the current line.
+    line_start: RefCell<usize>,
+    // where the token being scanned begins.
+    start_line: RefCell<usize>,
+    start_column: RefCell<usize>,
 }
 
 impl Scanner {
     pub fn new(source: &str) -> Self {
         Scanner {
-            source: source.to_string(),
+            source: source.chars().collect(),
             tokens: Vec::new().into(),
             start: 0.into(),
             current: 0.into(),
             line: 1.into(),
+            line_start: 0.into(),
+            start_line: 1.into(),
+            start_column: 1.into(),
         }
     }
 
     pub fn scan_tokens(&self) -> Result<Vec<Token>> {
         while !self.is_end() {
             *self.start.borrow_mut() = *self.current.borrow();
+            *self.start_line.borrow_mut() = *self.line.borrow();
+            *self.start_column.borrow_mut() = self.column();
             self.scan_token()?;
         }
 
@@ -55,6 +107,7 @@ impl Scanner {
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: *self.line.borrow(),
+            column: self.column(),
         }); //todo
         Ok(self.tokens.borrow().clone())
     }
@@ -68,12 +121,64 @@ impl Scanner {
             ')' => self.add_token(RightParen),
             '{' => self.add_token(LeftBrace),
             '}' => self.add_token(RightBrace),
+            '[' => self.add_token(LeftBracket),
+            ']' => self.add_token(RightBracket),
             ',' => self.add_token(COMMA),
-            '.' => self.add_token(DOT),
-            '-' => self.add_token(MINUS),
-            '+' => self.add_token(PLUS),
+            '.' => {
+                let token = if !self.is_match('.') {
+                    DOT
+                } else if self.is_match('=') {
+                    DotDotEqual
+                } else {
+                    DotDot
+                };
+                self.add_token(token);
+            }
+            '-' => {
+                let token = if self.is_match('-') {
+                    MinusMinus
+                } else if self.is_match('=') {
+                    MinusEqual
+                } else {
+                    MINUS
+                };
+                self.add_token(token);
+            }
+            '+' => {
+                let token = if self.is_match('+') {
+                    PlusPlus
+                } else if self.is_match('=') {
+                    PlusEqual
+                } else {
+                    PLUS
+                };
+                self.add_token(token);
+            }
             ';' => self.add_token(SEMICOLON),
-            '*' => self.add_token(STAR),
+            '?' => self.add_token(QUESTION),
+            ':' => self.add_token(COLON),
+            '*' => {
+                let token = if self.is_match('*') {
+                    StarStar
+                } else if self.is_match('=') {
+                    StarEqual
+                } else {
+                    STAR
+                };
+                self.add_token(token);
+            }
+            '%' => {
+                let token = if self.is_match('=') {
+                    PercentEqual
+                } else {
+                    PERCENT
+                };
+                self.add_token(token);
+            }
+            '&' => self.add_token(AMPERSAND),
+            '|' => self.add_token(PIPE),
+            '^' => self.add_token(CARET),
+            '~' => self.add_token(TILDE),
             '!' => {
                 let token = if self.is_match('=') { BangEqual } else { BANG };
                 self.add_token(token);
@@ -81,18 +186,28 @@ impl Scanner {
             '=' => {
                 let token = if self.is_match('=') {
                     EqualEqual
+                } else if self.is_match('>') {
+                    FatArrow
                 } else {
                     EQUAL
                 };
                 self.add_token(token);
             }
             '<' => {
-                let token = if self.is_match('=') { LessEqual } else { LESS };
+                let token = if self.is_match('=') {
+                    LessEqual
+                } else if self.is_match('<') {
+                    LessLess
+                } else {
+                    LESS
+                };
                 self.add_token(token);
             }
             '>' => {
                 let token = if self.is_match('=') {
                     GreaterEqual
+                } else if self.is_match('>') {
+                    GreaterGreater
                 } else {
                     GREATER
                 };
@@ -100,14 +215,12 @@ impl Scanner {
             }
             '/' => {
                 if self.is_match('/') {
-                    // comments
-                    let iter = self.source.chars();
-                    for c in iter {
-                        if c == '\n' || self.is_end() {
-                            break;
-                        }
+                    // comments run to the end of the line.
+                    while self.peek(0) != '\n' && !self.is_end() {
                         self.advance();
                     }
+                } else if self.is_match('=') {
+                    self.add_token(SlashEqual);
                 } else {
                     // slash
                     self.add_token(SLASH);
@@ -116,38 +229,57 @@ impl Scanner {
             ' ' | '\r' | '\t' => {
                 // ignore
             }
-            '\n' => *self.line.borrow_mut() += 1,
-            '"' => self.deal_string(),
+            '\n' => {
+                // advance() already moved to the next line.
+            }
+            '"' => self.deal_string()?,
             '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => self.deal_number(),
 
             _ => {
                 if Self::is_alpha_underline(self.previous()) {
                     self.deal_identifier();
+                } else {
+                    return Err(self.error(&format!("Unexpected character '{}'.", c)));
                 }
             }
         }
         Ok(())
     }
 
-    fn deal_string(&self) {
+    fn deal_string(&self) -> Result<()> {
         while self.peek(0) != '"' && !self.is_end() {
-            if self.peek(0) == '\n' {
-                *self.line.borrow_mut() += 1;
-            }
             self.advance();
         }
 
         if self.is_end() {
-            todo!();
+            return Err(self.error(UNTERMINATED_STRING));
         }
 
         // The '"'
         self.advance();
 
         // trim quotes.
-        let value = &self.source[*self.start.borrow() + 1..*self.current.borrow() - 1];
+        let value: String = self.source[*self.start.borrow() + 1..*self.current.borrow() - 1]
+            .iter()
+            .collect();
 
-        self.add_literal_token(TokenType::STRING, value);
+        self.add_literal_token(TokenType::STRING, &value);
+        Ok(())
+    }
+
+    // spans from the start of the token being scanned to the current char.
+    fn error(&self, msg: &str) -> anyhow::Error {
+        MyError::ScanError(SyntaxError {
+            message: msg.to_string(),
+            location: String::new(),
+            span: Span {
+                line: *self.start_line.borrow(),
+                column: *self.start_column.borrow(),
+                end_line: *self.line.borrow(),
+                end_column: self.column(),
+            },
+        })
+        .into()
     }
 
     fn deal_number(&self) {
@@ -164,7 +296,7 @@ impl Scanner {
         }
 
         let value = self.get_current_value();
-        self.add_literal_token(TokenType::NUMBER, value)
+        self.add_literal_token(TokenType::NUMBER, &value)
     }
 
     fn deal_identifier(&self) {
@@ -172,7 +304,7 @@ impl Scanner {
             self.advance();
         }
         let text = self.get_current_value();
-        let value_type = match MAP.get(text) {
+        let value_type = match MAP.get(text.as_str()) {
             Some(t) => *t,
             None => TokenType::IDENTIFIER,
         };
@@ -180,16 +312,18 @@ impl Scanner {
         self.add_token(value_type)
     }
 
-    fn get_current_value(&self) -> &str {
-        &self.source[*self.start.borrow()..*self.current.borrow()]
+    fn get_current_value(&self) -> String {
+        self.source[*self.start.borrow()..*self.current.borrow()]
+            .iter()
+            .collect()
     }
 
     fn is_digit(c: char) -> bool {
-        c >= '0' && c <= '9'
+        c.is_ascii_digit()
     }
 
     fn is_alpha_underline(c: char) -> bool {
-        (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+        c.is_ascii_alphabetic() || c == '_'
     }
 
     fn is_alpha_underline_num(c: char) -> bool {
@@ -201,14 +335,14 @@ impl Scanner {
             return false;
         }
 
-        let current_char = self.source.chars().nth(*self.current.borrow()).unwrap();
+        let current_char = self.source[*self.current.borrow()];
         if current_char != expected {
             return false;
         }
 
         // ok
         *self.current.borrow_mut() += 1; // maybe TODO. not update current??
-        return true;
+        true
     }
 
     fn peek(&self, offset: usize) -> char {
@@ -216,15 +350,15 @@ impl Scanner {
         if self.is_end() || nth >= self.source.len() {
             return '\0';
         }
-        return self.source.chars().nth(nth).unwrap();
+        self.source[nth]
     }
 
     fn previous(&self) -> char {
         let nth = *self.current.borrow() - 1;
-        if self.is_end() || nth >= self.source.len() {
+        if nth >= self.source.len() {
             return '\0';
         }
-        return self.source.chars().nth(nth).unwrap();
+        self.source[nth]
     }
 
     fn add_literal_token(&self, token_type: TokenType, literal: &str) {
@@ -240,9 +374,10 @@ impl Scanner {
         };
         self.tokens.borrow_mut().push(Token {
             token_type,
-            lexeme: text.to_string(),
+            lexeme: text,
             literal,
-            line: *self.line.borrow(),
+            line: *self.start_line.borrow(),
+            column: *self.start_column.borrow(),
         })
     }
 
@@ -250,19 +385,96 @@ impl Scanner {
         let text = self.get_current_value();
         self.tokens.borrow_mut().push(Token {
             token_type,
-            lexeme: text.to_string(),
+            lexeme: text,
             literal: Type::Nil,
-            line: *self.line.borrow(),
+            line: *self.start_line.borrow(),
+            column: *self.start_column.borrow(),
         })
     }
 
     fn advance(&self) -> char {
-        let current_char = self.source.chars().nth(*self.current.borrow()).unwrap();
+        let current_char = self.source[*self.current.borrow()];
         *self.current.borrow_mut() += 1;
+        if current_char == '\n' {
+            *self.line.borrow_mut() += 1;
+            *self.line_start.borrow_mut() = *self.current.borrow();
+        }
         current_char
     }
 
+    fn column(&self) -> usize {
+        *self.current.borrow() - *self.line_start.borrow() + 1
+    }
+
     fn is_end(&self) -> bool {
         *self.current.borrow() >= self.source.len()
     }
 }
+
+// whether `source` stops partway through a statement: inside a string, with an open
+// bracket, or after an operator that needs a right-hand side. the REPL keeps reading
+// while this holds; anything else, valid or not, is left for the parser to judge.
+pub fn is_incomplete(source: &str) -> bool {
+    use TokenType::*;
+    let tokens = match Scanner::new(source).scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            return matches!(
+                e.downcast_ref::<MyError>(),
+                Some(MyError::ScanError(error)) if error.message == UNTERMINATED_STRING
+            )
+        }
+    };
+
+    let mut depth = 0;
+    for token in &tokens {
+        match token.token_type {
+            LeftParen | LeftBrace | LeftBracket => depth += 1,
+            RightParen | RightBrace | RightBracket => depth -= 1,
+            _ => {}
+        }
+    }
+
+    let last = tokens.iter().rev().find(|t| t.token_type != EOF);
+    let trailing_operator = last.is_some_and(|t| {
+        matches!(
+            t.token_type,
+            COMMA
+                | DOT
+                | MINUS
+                | PLUS
+                | QUESTION
+                | COLON
+                | SLASH
+                | STAR
+                | PERCENT
+                | AMPERSAND
+                | PIPE
+                | CARET
+                | TILDE
+                | BANG
+                | BangEqual
+                | EQUAL
+                | EqualEqual
+                | GREATER
+                | GreaterEqual
+                | LESS
+                | LessEqual
+                | StarStar
+                | LessLess
+                | GreaterGreater
+                | PlusEqual
+                | MinusEqual
+                | StarEqual
+                | SlashEqual
+                | PercentEqual
+                | FatArrow
+                | DotDot
+                | DotDotEqual
+                | AND
+                | OR
+        )
+    });
+
+    depth > 0 || trailing_operator
+}
@@ -1,10 +1,12 @@
 use crate::environment::Environment;
 use crate::errors::MyError;
 use crate::expr::Expr;
-use crate::interpreter::Interpreter;
+use crate::interpreter::{Flow, Interpreter};
+use crate::pattern::Pattern;
 use crate::tokens::{Token, Type};
 use anyhow::Result;
-use std::iter::zip;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Stmt {
@@ -24,7 +26,33 @@ pub enum Stmt {
         body: Box<Stmt>,
     },
     Block(Vec<Stmt>),
-    Break,
+    Break {
+        keyword: Token,
+    },
+    Return {
+        keyword: Token,
+        value: Expr,
+    },
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    Try {
+        body: Vec<Stmt>,
+        catch_clause: Option<CatchClause>,
+        finally_body: Option<Vec<Stmt>>,
+    },
+    // `path` is resolved relative to the importing file, `name` is what gets bound.
+    Import {
+        keyword: Token,
+        path: String,
+        name: String,
+    },
+    Match {
+        keyword: Token,
+        value: Expr,
+        arms: Vec<MatchArm>,
+    },
     Function {
         name: Token,
         params: Vec<Token>,
@@ -33,38 +61,118 @@ pub enum Stmt {
     NULL,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Stmt,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CatchClause {
+    pub name: Token,
+    pub body: Vec<Stmt>,
+}
+
 pub trait Callable {
     fn arity(&self) -> Result<usize>;
-    fn call(&self, interpreter: &Interpreter, arguments: &[Type]) -> Result<()>;
+    fn call(&self, interpreter: &Interpreter, arguments: &[Type]) -> Result<Type>;
 }
 
 // create a thin wrapper.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone)]
 pub struct LoxFunction {
     pub declaration: Stmt,
+    // scope the function was declared in.
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+// the closure may hold the function itself, so it is left out of Debug and compared by identity.
+impl std::fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoxFunction")
+            .field("declaration", &self.declaration)
+            .finish()
+    }
+}
+
+impl PartialEq for LoxFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.declaration == other.declaration && Rc::ptr_eq(&self.closure, &other.closure)
+    }
 }
 
 impl Callable for LoxFunction {
     fn arity(&self) -> Result<usize> {
         match &self.declaration {
-            Stmt::Function { name, params, body } => Ok(params.len()),
+            Stmt::Function { params, .. } => Ok(params.len()),
             _ => Err(MyError::NotCallableError.into()),
         }
     }
 
-    fn call(&self, interpreter: &Interpreter, arguments: &[Type]) -> Result<()> {
-        let environment = Environment::new(Some(interpreter.globals.clone()));
+    fn call(&self, interpreter: &Interpreter, arguments: &[Type]) -> Result<Type> {
+        let environment = Environment::new(Some(self.closure.clone()));
         match &self.declaration {
-            Stmt::Function { name, params, body } => {
-                params
-                    .iter()
-                    .zip(arguments.iter())
-                    .map(|(x, y)| environment.define(&x.lexeme, y))
-                    .collect::<Vec<_>>();
-
-                interpreter.execute_block(body, environment)
+            Stmt::Function { params, body, .. } => {
+                for (param, argument) in params.iter().zip(arguments.iter()) {
+                    environment.define(&param.lexeme, argument);
+                }
+
+                match interpreter.execute_block(body, environment)? {
+                    Flow::Return(value) => Ok(value),
+                    Flow::Normal => Ok(Type::Nil),
+                    Flow::Break => Err(MyError::BreakNotInLoop.into()),
+                }
             }
             _ => Err(MyError::NotCallableError.into()),
         }
     }
 }
+
+pub type NativeFn = dyn Fn(&Interpreter, &[Type]) -> Result<Type>;
+
+// functions implemented in rust and registered by the host.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: usize,
+    pub function: Rc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new<F>(name: &str, arity: usize, function: F) -> Self
+    where
+        F: Fn(&Interpreter, &[Type]) -> Result<Type> + 'static,
+    {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+}
+
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl Callable for NativeFunction {
+    fn arity(&self) -> Result<usize> {
+        Ok(self.arity)
+    }
+
+    fn call(&self, interpreter: &Interpreter, arguments: &[Type]) -> Result<Type> {
+        (self.function)(interpreter, arguments)
+    }
+}
@@ -1,6 +1,9 @@
+use crate::diagnostic::ErrorFormat;
 use crate::environment::Environment;
+use crate::errors::{MyError, Span};
 use crate::expr::Expr;
 use crate::interpreter::Interpreter;
+use crate::output::SharedBuffer;
 use crate::parser::Parser;
 use crate::scanner::Scanner;
 use crate::stmt::Stmt;
@@ -14,6 +17,7 @@ fn test_token_to_str() {
         lexeme: "+".to_string(),
         literal: Type::Nil,
         line: 2,
+        column: 0,
     };
     assert_eq!(result.to_string(), "PLUS + Nil");
 }
@@ -29,18 +33,21 @@ fn test_add_single_token() {
             lexeme: "(".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 1,
         },
         Token {
             token_type: STAR,
             lexeme: "*".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 2,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 3,
         },
     ];
     assert_eq!(result, expected);
@@ -57,12 +64,14 @@ fn test_add_two_tokens() {
             lexeme: "!=".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 1,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 3,
         },
     ];
     assert_eq!(result, expected);
@@ -79,24 +88,28 @@ fn test_comments_tokens() {
             lexeme: "!=".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 1,
         },
         Token {
             token_type: SLASH,
             lexeme: "/".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 3,
         },
         Token {
             token_type: LeftParen,
             lexeme: "(".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 4,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 32,
         },
     ];
     assert_eq!(result, expected);
@@ -113,12 +126,14 @@ fn test_string_literal_tokens() {
             lexeme: "\"asdf\"".to_string(),
             literal: Type::String("asdf".to_string()),
             line: 1,
+            column: 1,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 7,
         },
     ];
     assert_eq!(result, expected);
@@ -135,12 +150,14 @@ fn test_number_tokens() {
             lexeme: "123.53".to_string(),
             literal: Type::Number("123.53".parse::<f64>().unwrap()),
             line: 1,
+            column: 1,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 13,
         },
     ];
     assert_eq!(result, expected);
@@ -157,12 +174,14 @@ fn test_identifier_tokens() {
             lexeme: "asdf98".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 1,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 7,
         },
     ];
     assert_eq!(result, expected);
@@ -179,12 +198,14 @@ fn test_reserved_tokens() {
             lexeme: "class".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 1,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 19,
         },
     ];
     assert_eq!(result, expected);
@@ -200,30 +221,35 @@ fn test_parser_primary() -> Result<()> {
             lexeme: "3".to_string(),
             literal: Type::Number("3".parse::<f64>().unwrap()),
             line: 1,
+            column: 0,
         },
         Token {
             token_type: PLUS,
             lexeme: "+".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         Token {
             token_type: NUMBER,
             lexeme: "4".to_string(),
             literal: Type::Number("4".parse::<f64>().unwrap()),
             line: 1,
+            column: 0,
         },
         Token {
             token_type: SEMICOLON,
             lexeme: ";".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         Token {
             token_type: EOF,
             lexeme: "".to_string(),
             literal: Type::Nil,
             line: 2,
+            column: 0,
         },
     ];
     let expected = vec![Stmt::ExprStmt(Binary {
@@ -233,6 +259,7 @@ fn test_parser_primary() -> Result<()> {
             lexeme: "+".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(Type::Number("4".parse::<f64>().unwrap()))),
     })];
@@ -267,6 +294,7 @@ fn test_evalute_unary() -> Result<()> {
             lexeme: "-".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(Number(3.0))),
     };
@@ -291,6 +319,7 @@ fn test_evalute_grouping() -> Result<()> {
             lexeme: "+".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(Number(3.0))),
     }));
@@ -316,6 +345,7 @@ fn test_evalute_binary_1() -> Result<()> {
                 lexeme: "+".to_string(),
                 literal: Type::Nil,
                 line: 1,
+                column: 0,
             },
             right: Box::new(Literal(Number(5.0))),
         }))),
@@ -324,6 +354,7 @@ fn test_evalute_binary_1() -> Result<()> {
             lexeme: "-".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(Number(5.0))),
     };
@@ -348,6 +379,7 @@ fn test_evalute_binary_2() -> Result<()> {
             lexeme: ">".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(Number(3.0))),
     };
@@ -372,6 +404,7 @@ fn test_evalute_binary_3() -> Result<()> {
             lexeme: "/".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(Number(3.0))),
     };
@@ -396,6 +429,7 @@ fn test_evalute_binary_4() -> Result<()> {
             lexeme: "+".to_string(),
             literal: Type::Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(String("123".to_string()))),
     };
@@ -422,6 +456,7 @@ fn test_environment_var() -> Result<()> {
             lexeme: "a".to_string(),
             literal: Nil,
             line: 1,
+            column: 0,
         },
         initializer: Literal(Number(3.0)),
     }];
@@ -434,7 +469,7 @@ fn test_environment_var() -> Result<()> {
     interpreter.interpret(stmts)?;
     let result = interpreter.get_environment()?;
 
-    assert_eq!(expected, result);
+    assert_eq!(expected, &*result);
 
     Ok(())
 }
@@ -454,6 +489,7 @@ fn test_environment_assign() -> Result<()> {
                 lexeme: "a".to_string(),
                 literal: Nil,
                 line: 1,
+                column: 0,
             },
             initializer: Literal(Number(1.0)),
         },
@@ -463,6 +499,7 @@ fn test_environment_assign() -> Result<()> {
                 lexeme: "a".to_string(),
                 literal: Nil,
                 line: 1,
+                column: 0,
             },
             value: Box::new(Literal(Number(2.0))),
         }),
@@ -476,7 +513,7 @@ fn test_environment_assign() -> Result<()> {
     interpreter.interpret(stmts)?;
     let result = interpreter.get_environment()?;
 
-    assert_eq!(expected, result);
+    assert_eq!(expected, &*result);
 
     Ok(())
 }
@@ -493,6 +530,7 @@ fn test_evalute_logical() -> Result<()> {
             lexeme: "or".into(),
             literal: Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(Number(3.0))),
     };
@@ -517,6 +555,7 @@ fn test_evalute_logical2() -> Result<()> {
             lexeme: "or".into(),
             literal: Nil,
             line: 1,
+            column: 0,
         },
         right: Box::new(Literal(String("yes".into()))),
     };
@@ -528,3 +567,836 @@ fn test_evalute_logical2() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_print_to_output() -> Result<()> {
+    let out = SharedBuffer::new();
+    let err = SharedBuffer::new();
+    let interpreter = Interpreter::with_output(false, Box::new(out.clone()), Box::new(err.clone()));
+    interpreter.run("print 1 + 2; print \"a\"; 3;")?;
+
+    assert_eq!(out.contents(), "3\na\n");
+    assert_eq!(err.contents(), "");
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_echo_to_output() -> Result<()> {
+    let out = SharedBuffer::new();
+    let interpreter =
+        Interpreter::with_output(true, Box::new(out.clone()), Box::new(SharedBuffer::new()));
+    interpreter.run("1 + 2;")?;
+
+    assert_eq!(out.contents(), "3\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_echo_uses_repr() -> Result<()> {
+    let out = SharedBuffer::new();
+    let interpreter =
+        Interpreter::with_output(true, Box::new(out.clone()), Box::new(SharedBuffer::new()));
+    interpreter.run("\"1\"; 1; nil; print \"1\";")?;
+
+    assert_eq!(out.contents(), "\"1\"\n1\nnil\n1\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_repr() -> Result<()> {
+    let source = r#"
+        fun add(a, b) { return a + b; }
+        print repr("back\slash
+two");
+        print repr(["a", 1, [nil, true]]);
+        print repr(add);
+        print repr(clock);
+        print repr(Error("bad"));
+        print str("a") + repr("a");
+    "#;
+    let expected = concat!(
+        "\"back\\\\slash\\ntwo\"\n",
+        "[\"a\", 1, [nil, true]]\n",
+        "<fn add/2>\n",
+        "<native fn clock/0>\n",
+        "<error Error \"bad\">\n",
+        "a\"a\"\n",
+    );
+    assert_eq!(run_capture(source)?, expected);
+    assert_eq!(Type::String("a\tb\n".into()).repr(), "\"a\\tb\\n\"");
+
+    Ok(())
+}
+
+#[test]
+fn test_errors_to_error_output() {
+    let out = SharedBuffer::new();
+    let err = SharedBuffer::new();
+    let interpreter = Interpreter::with_output(false, Box::new(out.clone()), Box::new(err.clone()));
+    let result = interpreter.run("print ;");
+    interpreter.report_error(&result.unwrap_err());
+
+    assert_eq!(out.contents(), "");
+    assert!(err
+        .contents()
+        .contains("[line 1] Error at ';': Expected expression."));
+}
+
+#[test]
+fn test_json_diagnostics() {
+    let err = SharedBuffer::new();
+    let interpreter =
+        Interpreter::with_output(false, Box::new(SharedBuffer::new()), Box::new(err.clone()));
+    interpreter.set_error_format(ErrorFormat::Json);
+    interpreter.run("match (3) { 1 => print 1; }").unwrap();
+    let result = interpreter.run("var a = 1;\nprint -a + nil;");
+    interpreter.report_error(&result.unwrap_err());
+
+    let lines = err
+        .contents()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect::<Vec<serde_json::Value>>();
+    assert_eq!(lines[0]["severity"], "warning");
+    assert_eq!(lines[0]["message"], "no match arm for value 3.");
+    // the operator that failed, not the operand evaluated last.
+    assert_eq!(lines[1]["severity"], "error");
+    assert_eq!(lines[1]["code"], "CastError");
+    assert_eq!(
+        (&lines[1]["line"], &lines[1]["column"]),
+        (&2.into(), &10.into())
+    );
+}
+
+#[test]
+fn test_syntax_error_span() {
+    let MyError::ParseError(error) = run_error("print (1 +\n  2;") else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(error.message, "Expect ')' after expression.");
+    assert_eq!(
+        error.span,
+        Span {
+            line: 2,
+            column: 4,
+            end_line: 2,
+            end_column: 5
+        }
+    );
+}
+
+#[test]
+fn test_incomplete_input() {
+    use crate::scanner::is_incomplete;
+    assert!(is_incomplete("fun f() {\n"));
+    assert!(is_incomplete("print max(1,"));
+    assert!(is_incomplete("var xs = [1, 2"));
+    assert!(is_incomplete("print \"two\n"));
+    assert!(is_incomplete("var a = 1 +"));
+    assert!(is_incomplete("if (a and"));
+
+    assert!(!is_incomplete(""));
+    assert!(!is_incomplete("print 1;"));
+    assert!(!is_incomplete("fun f() {}\n"));
+    assert!(!is_incomplete("a++"));
+    // errors are for the parser to report.
+    assert!(!is_incomplete("print (1));"));
+    assert!(!is_incomplete("print 1 # 2;"));
+}
+
+#[test]
+fn test_completions() {
+    use crate::scanner::completions;
+    let names = vec![
+        "price".to_string(),
+        "print".to_string(),
+        "total".to_string(),
+    ];
+
+    assert_eq!(
+        completions("pr", 2, &names),
+        (0, vec!["price".to_string(), "print".to_string()])
+    );
+    // only the identifier before the cursor counts.
+    assert_eq!(
+        completions("var x = tot + 1;", 11, &names),
+        (8, vec!["total".to_string()])
+    );
+    assert_eq!(completions("", 0, &names), (0, Vec::new()));
+    assert_eq!(completions("print ", 6, &names), (6, Vec::new()));
+}
+
+#[test]
+fn test_highlight() {
+    use crate::color::highlight;
+    assert_eq!(
+        highlight("var a = 1; // one"),
+        "\x1b[35mvar\x1b[0m a = \x1b[33m1\x1b[0m; \x1b[90m// one\x1b[0m"
+    );
+    assert_eq!(
+        highlight("print nil or \"é\";"),
+        "\x1b[35mprint\x1b[0m \x1b[36mnil\x1b[0m \x1b[35mor\x1b[0m \x1b[32m\"é\"\x1b[0m;"
+    );
+    // input that does not scan yet is colored as far as it goes.
+    assert_eq!(
+        highlight("if (s == \"ab"),
+        "\x1b[35mif\x1b[0m (s == \x1b[32m\"ab\x1b[0m"
+    );
+    assert_eq!(highlight("x # y"), "x # y");
+    // every keyword the scanner knows is colored.
+    for keyword in crate::scanner::keywords() {
+        assert!(highlight(keyword).starts_with('\x1b'), "{keyword}");
+    }
+}
+
+#[test]
+fn test_colored_values() {
+    use crate::color::value;
+    assert_eq!(value(&Type::String("1".into())), "\x1b[32m\"1\"\x1b[0m");
+    assert_eq!(value(&Type::Number(1.0)), "\x1b[33m1\x1b[0m");
+    let list = Type::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
+        Type::Nil,
+        Type::Bool(true),
+    ])));
+    assert_eq!(value(&list), "[\x1b[36mnil\x1b[0m, \x1b[36mtrue\x1b[0m]");
+}
+
+fn run_capture(source: &str) -> Result<String> {
+    let out = SharedBuffer::new();
+    let interpreter =
+        Interpreter::with_output(false, Box::new(out.clone()), Box::new(SharedBuffer::new()));
+    interpreter.run(source)?;
+
+    Ok(out.contents())
+}
+
+fn run_error(source: &str) -> crate::errors::MyError {
+    let err = run_capture(source).unwrap_err();
+    err.downcast::<crate::errors::MyError>().unwrap()
+}
+
+#[test]
+fn test_nil_initializer_is_nil() -> Result<()> {
+    assert_eq!(run_capture("var a = nil; print a;")?, "nil\n");
+    assert_eq!(run_capture("print nil;")?, "nil\n");
+    assert_eq!(run_capture("var a = nil; print a == nil;")?, "true\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_var_without_initializer() -> Result<()> {
+    assert_eq!(run_capture("var a; a = 1; print a;")?, "1\n");
+    assert_eq!(run_capture("var a; a = nil; print a;")?, "nil\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_read_uninitialized_var() {
+    use crate::errors::MyError::*;
+    assert!(matches!(run_error("var a; print a;"), EnValueNotInitError(name) if name == "a"));
+    assert!(matches!(
+        run_error("var a = 1; { var a; print a; }"),
+        EnValueNotInitError(_)
+    ));
+    assert!(matches!(run_error("print b;"), EnValueNotFoundError(_)));
+}
+
+#[test]
+fn test_nil_argument() -> Result<()> {
+    assert_eq!(run_capture("fun f(a) { print a; } f(nil);")?, "nil\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_format_number() {
+    use crate::tokens::format_number;
+    assert_eq!(format_number(3.0), "3");
+    assert_eq!(format_number(-12.0), "-12");
+    assert_eq!(format_number(0.1 + 0.2), "0.30000000000000004");
+    assert_eq!(format_number(2.5), "2.5");
+    assert_eq!(format_number(1e20), "100000000000000000000");
+    assert_eq!(format_number(1e21), "1e+21");
+    assert_eq!(format_number(1.5e300), "1.5e+300");
+    assert_eq!(format_number(0.000001), "0.000001");
+    assert_eq!(format_number(1e-7), "1e-7");
+    assert_eq!(format_number(0.0), "0");
+    assert_eq!(format_number(-0.0), "-0");
+    assert_eq!(format_number(f64::NAN), "nan");
+    assert_eq!(format_number(f64::INFINITY), "inf");
+    assert_eq!(format_number(f64::NEG_INFINITY), "-inf");
+}
+
+#[test]
+fn test_print_and_str_numbers() -> Result<()> {
+    assert_eq!(
+        run_capture("print 10 / 4; print 7 * 3; print -0;")?,
+        "2.5\n21\n-0\n"
+    );
+    assert_eq!(run_capture("print str(1.50) + \"!\";")?, "1.5!\n");
+    assert_eq!(run_capture("print str(nil) + str(true);")?, "niltrue\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_modulo_and_power() -> Result<()> {
+    assert_eq!(
+        run_capture("print 7 % 3; print -7 % 3; print 5.5 % 2;")?,
+        "1\n-1\n1.5\n"
+    );
+    assert_eq!(run_capture("print 2 ** 10; print 2 ** -1;")?, "1024\n0.5\n");
+    // right-associative, tighter than unary minus and factor.
+    assert_eq!(
+        run_capture("print 2 ** 3 ** 2; print -2 ** 2; print 3 * 2 ** 2;")?,
+        "512\n-4\n12\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_integer_division() -> Result<()> {
+    assert_eq!(
+        run_capture("print idiv(7, 2); print idiv(-7, 2); print idiv(7, 2) * 2 + 7 % 2;")?,
+        "3\n-3\n7\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_modulo_by_zero() {
+    use crate::errors::MyError::*;
+    assert!(matches!(run_error("print 1 % 0;"), DividedbyzeroError));
+    assert!(matches!(run_error("print idiv(1, 0);"), DividedbyzeroError));
+    assert!(matches!(run_error("print idiv(\"a\", 1);"), CastError(_)));
+}
+
+#[test]
+fn test_bitwise_operators() -> Result<()> {
+    assert_eq!(
+        run_capture("print 12 & 10; print 12 | 10; print 12 ^ 10; print ~5;")?,
+        "8\n14\n6\n-6\n"
+    );
+    assert_eq!(
+        run_capture("print 1 << 4; print 256 >> 4; print -16 >> 2;")?,
+        "16\n16\n-4\n"
+    );
+    // & binds tighter than ==, shifts tighter than comparison.
+    assert_eq!(
+        run_capture("print 6 & 3 == 2; print 1 << 2 < 5; print 1 | 2 ^ 3 & 4;")?,
+        "true\ntrue\n3\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_bitwise_rejects_fractions() {
+    use crate::errors::MyError::*;
+    assert!(matches!(run_error("print 1.5 & 1;"), CastError(_)));
+    assert!(matches!(run_error("print ~0.5;"), CastError(_)));
+    assert!(matches!(run_error("print 1 << 64;"), CastError(_)));
+    // 2^63 is one past i64::MAX and must not saturate to it.
+    assert!(matches!(
+        run_error("print 9223372036854775808 >> 0;"),
+        CastError(_)
+    ));
+    // while -2^63 is i64::MIN.
+    assert_eq!(
+        run_capture("print -9223372036854775808 | 0 == -9223372036854775808;").unwrap(),
+        "true\n"
+    );
+    assert!(matches!(run_error("print \"a\" | 1;"), CastError(_)));
+}
+
+#[test]
+fn test_compound_assignment() -> Result<()> {
+    let source = "var a = 10; a += 5; print a; a -= 3; print a; a *= 2; print a; a /= 4; print a; a %= 4; print a;";
+    assert_eq!(run_capture(source)?, "15\n12\n24\n6\n2\n");
+    assert_eq!(run_capture("var s = \"a\"; s += \"b\"; print s;")?, "ab\n");
+    // assignment expression yields the new value and is right-associative.
+    assert_eq!(
+        run_capture("var a = 1; var b = 2; a += b += 3; print a; print b;")?,
+        "6\n5\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_increment_decrement() -> Result<()> {
+    assert_eq!(
+        run_capture("var i = 1; print i++; print i; print ++i; print i--; print --i;")?,
+        "1\n2\n3\n3\n1\n"
+    );
+    assert_eq!(
+        run_capture("var n = 0; for (var i = 0; i < 3; i++) { n += i; } print n;")?,
+        "3\n"
+    );
+    // the prefix operand is just the variable, so `**` applies to the result.
+    assert_eq!(
+        run_capture("var a = 2; print ++a ** 2; print a; print --a ** 2;")?,
+        "9\n3\n4\n"
+    );
+    // subtracting a negative needs the space now that `--` is an operator.
+    assert_eq!(run_capture("print 1 - -1;")?, "2\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_compound_assignment_errors() {
+    use crate::errors::MyError::*;
+    assert!(matches!(
+        run_error("1 += 2;"),
+        InvalidAssignmentTargetError(_)
+    ));
+    assert!(matches!(run_error("++1;"), InvalidAssignmentTargetError(_)));
+    // `--` is one token, so this is no longer `1 - (-1)`.
+    assert!(matches!(
+        run_error("print 1--1;"),
+        InvalidAssignmentTargetError(_)
+    ));
+    assert!(matches!(run_error("var s = \"a\"; s++;"), CastError(_)));
+    assert!(matches!(run_error("b += 1;"), EnValueNotFoundError(_)));
+}
+
+#[test]
+fn test_conditional_expression() -> Result<()> {
+    assert_eq!(
+        run_capture("print true ? 1 : 2; print nil ? 1 : 2;")?,
+        "1\n2\n"
+    );
+    // right-associative.
+    assert_eq!(
+        run_capture("var n = 5; print n < 0 ? \"neg\" : n == 0 ? \"zero\" : \"pos\";")?,
+        "pos\n"
+    );
+    // lower precedence than `or`, higher than assignment.
+    assert_eq!(
+        run_capture("var a; a = false or true ? 1 : 2; print a;")?,
+        "1\n"
+    );
+    // only the selected branch is evaluated.
+    assert_eq!(
+        run_capture("var a = 0; var b = true ? a++ : a--; print a;")?,
+        "1\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_conditional_display() -> Result<()> {
+    let tokens = Scanner::new("a ? b : c ? d : e").scan_tokens()?;
+    let expr = Parser::new(&tokens).parse_expression()?;
+    assert_eq!(expr.to_string(), "a ? b : c ? d : e");
+
+    Ok(())
+}
+
+#[test]
+fn test_single_char_identifier_at_end() -> Result<()> {
+    let tokens = Scanner::new("a").scan_tokens()?;
+    assert_eq!(tokens[0].token_type, TokenType::IDENTIFIER);
+    assert_eq!(tokens.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_match_no_arm_warning() -> Result<()> {
+    let out = SharedBuffer::new();
+    let err = SharedBuffer::new();
+    let interpreter = Interpreter::with_output(false, Box::new(out.clone()), Box::new(err.clone()));
+    interpreter.run("match (3) {\n 1 => print 1;\n 2 => print 2;\n}")?;
+
+    assert_eq!(out.contents(), "");
+    assert_eq!(
+        err.contents(),
+        "[line 1] Warning: no match arm for value 3.\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_match_bindings_are_scoped() -> Result<()> {
+    let source =
+        "var x = \"outer\"; match (1) { x if x > 5 => print \"big\"; x => print x; } print x;";
+    assert_eq!(run_capture(source)?, "1\nouter\n");
+    // a failed alternative does not leak bindings.
+    let source = "match ([1, 2]) { [a, 3] | [_, a] => print a; }";
+    assert_eq!(run_capture(source)?, "2\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_match_break_in_loop() -> Result<()> {
+    let source = "var i = 0; while (true) { i = i + 1; match (i) { 3 => break; _ => print i; } }";
+    assert_eq!(run_capture(source)?, "1\n2\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_return_values() -> Result<()> {
+    assert_eq!(
+        run_capture("fun add(a, b) { return a + b; } print add(1, 2);")?,
+        "3\n"
+    );
+    assert_eq!(run_capture("fun f() { return; } print f();")?, "nil\n");
+    let source =
+        "fun find() { for (var i = 0; i < 10; i++) { if (i == 4) return i; } } print find();";
+    assert_eq!(run_capture(source)?, "4\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_break_exits_innermost_loop() -> Result<()> {
+    let source = "for (var i = 0; i < 2; i++) { while (true) { break; print \"no\"; } print i; }";
+    assert_eq!(run_capture(source)?, "0\n1\n");
+    let source = "while (true) { if (true) { break; } print \"after\"; } print \"done\";";
+    assert_eq!(run_capture(source)?, "done\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_control_flow_outside_scope() {
+    use crate::errors::MyError::*;
+    assert!(matches!(run_error("break;"), BreakNotInLoop));
+    assert!(matches!(run_error("return 1;"), ReturnNotInFunction));
+    assert!(matches!(
+        run_error("fun f() { break; } while (true) { f(); }"),
+        BreakNotInLoop
+    ));
+}
+
+#[test]
+fn test_uncaught_throw() {
+    let error = run_capture("throw \"oops\";").unwrap_err();
+    assert_eq!(error.to_string(), "Uncaught exception: oops");
+}
+
+#[test]
+fn test_thrown_values_are_released() -> Result<()> {
+    let interpreter = Interpreter::with_output(
+        false,
+        Box::new(SharedBuffer::new()),
+        Box::new(SharedBuffer::new()),
+    );
+    interpreter.run("fun fail() { throw [1, 2, 3]; }")?;
+    for _ in 0..3 {
+        assert!(interpreter.run("throw \"oops\";").is_err());
+    }
+    assert_eq!(interpreter.thrown_count(), 0);
+    for _ in 0..3 {
+        assert!(interpreter.eval("fail()").is_err());
+    }
+    assert_eq!(interpreter.thrown_count(), 0);
+
+    // a finally that returns or throws replaces the pending throw for good.
+    let source = "fun f() { try { throw 1; } finally { return 2; } } f(); f();
+        try { try { throw 3; } finally { throw 4; } } catch (e) {}";
+    interpreter.run(source)?;
+    assert_eq!(interpreter.thrown_count(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_finally_overrides() -> Result<()> {
+    let source = "fun f() { try { return 1; } finally { return 2; } } print f();";
+    assert_eq!(run_capture(source)?, "2\n");
+    // finally still runs when nothing catches the error.
+    let out = SharedBuffer::new();
+    let interpreter =
+        Interpreter::with_output(false, Box::new(out.clone()), Box::new(SharedBuffer::new()));
+    let result = interpreter.run("try { print 1 / 0; } finally { print \"cleanup\"; }");
+    assert!(result.is_err());
+    assert_eq!(out.contents(), "cleanup\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_catch_runtime_errors() -> Result<()> {
+    let source = "try { print undefined; } catch (e) { print e.kind; print e.message; }";
+    assert_eq!(
+        run_capture(source)?,
+        "EnValueNotFoundError\nUndefined variable undefined.\n"
+    );
+    let source =
+        "try { \"a\" - 1; } catch (e) { print e.kind; } try { nil.x; } catch (e) { print e.kind; }";
+    assert_eq!(run_capture(source)?, "CastError\nUndefinedPropertyError\n");
+    // the environment is restored after an error inside a block.
+    let source =
+        "var a = 1; try { var a = 2; { var a = 3; throw a; } } catch (e) { print e; } print a;";
+    assert_eq!(run_capture(source)?, "3\n1\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_closures() -> Result<()> {
+    let source = "fun make() { var n = 0; fun next() { n = n + 1; return n; } return next; }
+        var a = make(); var b = make(); a(); a(); print a(); print b();";
+    assert_eq!(run_capture(source)?, "3\n1\n");
+    // the closure sees its defining scope, not the caller's.
+    let source = "var x = \"global\"; fun show() { print x; } { var x = \"local\"; show(); }";
+    assert_eq!(run_capture(source)?, "global\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_string_natives() -> Result<()> {
+    let source = r#"
+        var s = "héllo, wörld";
+        print len(s);
+        print substring(s, 7, 12);
+        print indexOf(s, "wö");
+        print indexOf(s, "x");
+        print join(split("a,b,,c", ","), "|");
+        print len(split("añb", ""));
+        print trim("  x  ") + "!";
+        print upper(s);
+        print lower("ÀB");
+        print replace("a-b-c", "-", "+");
+        print startsWith(s, "hé");
+        print endsWith(s, "d");
+        print ord("é");
+        print chr(955);
+    "#;
+    assert_eq!(
+        run_capture(source)?,
+        "12\nwörld\n7\n-1\na|b||c\n3\nx!\nHÉLLO, WÖRLD\nàb\na+b+c\ntrue\ntrue\n233\nλ\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_string_native_errors() {
+    use crate::errors::MyError;
+
+    assert!(matches!(run_error("len(1);"), MyError::CastError(_)));
+    assert!(matches!(
+        run_error("substring(\"abc\", 2, 4);"),
+        MyError::IndexError(_)
+    ));
+    assert!(matches!(
+        run_error("substring(\"abc\", 1.5, 2);"),
+        MyError::CastError(_)
+    ));
+    assert!(matches!(run_error("upper(nil);"), MyError::CastError(_)));
+    assert!(matches!(run_error("ord(\"ab\");"), MyError::CastError(_)));
+    assert!(matches!(run_error("chr(-1);"), MyError::CastError(_)));
+    assert!(matches!(
+        run_error("trim(\"a\", \"b\");"),
+        MyError::ArityMismatchError(1, 2)
+    ));
+}
+
+#[test]
+fn test_comment_to_end_of_line() -> Result<()> {
+    let source = "print 1; // first\n// second line\nprint 2; // ünïcode";
+    assert_eq!(run_capture(source)?, "1\n2\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_math_module() -> Result<()> {
+    let source = r#"
+        print math.floor(-2.5);
+        print math.ceil(2.1);
+        print math.round(-2.5);
+        print math.abs(-3);
+        print math.sqrt(2) * math.sqrt(2) - 2 < 0.000001;
+        print math.pow(2, 0.5) == math.sqrt(2);
+        print math.exp(0) + math.log(math.E);
+        print math.sin(math.PI / 2);
+        print math.atan2(1, 1) * 4 == math.PI;
+        print math.min(3, -1);
+        print math.max(3, -1);
+        print math.E;
+    "#;
+    assert_eq!(
+        run_capture(source)?,
+        "-3\n3\n-3\n3\ntrue\ntrue\n2\n1\ntrue\n-1\n3\n2.718281828459045\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_math_random_is_seedable() -> Result<()> {
+    let source = r#"
+        math.seed(7);
+        var first = [math.random(), math.random(), math.random()];
+        math.seed(7);
+        print first == [math.random(), math.random(), math.random()];
+        var r = math.random();
+        print r >= 0 and r < 1;
+    "#;
+    assert_eq!(run_capture(source)?, "true\ntrue\n");
+
+    use crate::errors::MyError;
+    assert!(matches!(
+        run_error("math.sqrt(\"4\");"),
+        MyError::CastError(_)
+    ));
+    assert!(matches!(
+        run_error("math.pow(2);"),
+        MyError::ArityMismatchError(2, 1)
+    ));
+    assert!(matches!(
+        run_error("math.nope;"),
+        MyError::UndefinedPropertyError(_)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_number() -> Result<()> {
+    let source = r#"
+        print parseNumber("42");
+        print parseNumber(" -1.5e3 ");
+        print parseNumber("12px");
+        print parseNumber("inf");
+        print parseNumber("");
+    "#;
+    assert_eq!(run_capture(source)?, "42\n-1500\nnil\nnil\nnil\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_exit_is_not_catchable() -> Result<()> {
+    use crate::errors::MyError;
+
+    let source =
+        "fun f() { try { exit(2); } catch (e) { print e; } } try { f(); } finally { print 1; }";
+    assert!(matches!(run_error(source), MyError::Exit(2)));
+    assert!(matches!(run_error("exit(1.5);"), MyError::CastError(_)));
+    // a catch block calling exit still skips finally.
+    let out = SharedBuffer::new();
+    let interpreter =
+        Interpreter::with_output(false, Box::new(out.clone()), Box::new(SharedBuffer::new()));
+    let source = "try { throw 1; } catch (e) { print e; exit(0); } finally { print 2; }";
+    assert!(interpreter.run(source).is_err());
+    assert_eq!(out.contents(), "1\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_args_default_empty() -> Result<()> {
+    assert_eq!(run_capture("print args;")?, "[]\n");
+
+    let interpreter = Interpreter::new(false);
+    interpreter.set_args(&["a", "b"]);
+    assert_eq!(interpreter.eval("len(args)")?, Type::Number(2.0));
+
+    Ok(())
+}
+
+#[test]
+fn test_scan_errors() {
+    use crate::errors::MyError;
+
+    assert!(matches!(run_error("print \"abc;"), MyError::ScanError(_)));
+    assert!(matches!(run_error("var a = 1 # 2;"), MyError::ScanError(_)));
+}
+
+#[test]
+fn test_exit_codes() {
+    use crate::errors::{exit_code, MyError};
+
+    let code = |source: &str| exit_code(&run_capture(source).unwrap_err());
+    assert_eq!(code("print \"abc;"), 65);
+    assert_eq!(code("print (1;"), 65);
+    assert_eq!(code("1 = 2;"), 65);
+    assert_eq!(code("break;"), 65);
+    assert_eq!(code("print nope;"), 70);
+    assert_eq!(code("throw 1;"), 70);
+    assert_eq!(code("exit(3);"), 3);
+    assert_eq!(exit_code(&MyError::IoError("x".into()).into()), 74);
+}
+
+#[test]
+fn test_token_positions() -> Result<()> {
+    let tokens = Scanner::new("var a =\n  \"two\nlines\" + 1;").scan_tokens()?;
+    let positions: Vec<_> = tokens.iter().map(|t| (t.line, t.column)).collect();
+    // a token is placed where it starts.
+    assert_eq!(
+        positions,
+        [
+            (1, 1),
+            (1, 5),
+            (1, 7),
+            (2, 3),
+            (3, 8),
+            (3, 10),
+            (3, 11),
+            (3, 12)
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_static_checks() -> Result<()> {
+    let check = |source: &str| -> Result<Vec<String>> {
+        let tokens = Scanner::new(source).scan_tokens()?;
+        let stmts = Parser::new(&tokens).parse()?;
+        Ok(crate::checker::check(&stmts)
+            .iter()
+            .map(|e| e.to_string())
+            .collect())
+    };
+
+    // globals may be redefined and loops may hold functions that return.
+    let ok = "var a = 1; var a = a; while (true) { fun f() { return 1; } break; }
+        match (1) { [x] | [x, _] => print x; _ => print 0; }";
+    assert!(check(ok)?.is_empty());
+
+    let errors = check(
+        "fun f(a, a) { var b = 1; var b = 2; } while (true) { fun g() { break; } }
+        { var c = c; } return 1; try {} catch (e) { var e = 1; }",
+    )?;
+    assert_eq!(errors.len(), 6);
+    assert!(errors[0].contains("'a': Already a variable"));
+    assert!(errors[1].contains("'b': Already a variable"));
+    assert!(errors[2].contains("'break': Can't use 'break' outside of a loop."));
+    assert!(errors[3].contains("'c': Can't read local variable in its own initializer."));
+    assert!(errors[4].contains("'return': Can't return from top-level code."));
+    assert!(errors[5].contains("'e': Already a variable"));
+
+    Ok(())
+}
+
+#[test]
+fn test_ast_dump() -> Result<()> {
+    let tokens = Scanner::new("if (a and !b) print -1; else x = [1, \"s\", nil];").scan_tokens()?;
+    let stmts = Parser::new(&tokens).parse()?;
+    assert_eq!(
+        crate::dump::ast_text(&stmts),
+        "(if (and a (! b)) (print (- 1)) (expression (assign x (list [1 \"s\" nil]))))\n"
+    );
+
+    Ok(())
+}
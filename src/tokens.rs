@@ -1,5 +1,8 @@
-use crate::stmt::LoxFunction;
-use crate::stmt::Stmt;
+use crate::errors::ErrorValue;
+use crate::module::Module;
+use crate::stmt::{LoxFunction, NativeFunction, Stmt};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Type {
@@ -9,23 +12,32 @@ pub enum Type {
     Number(f64),
     String(String),
     Fun(Box<LoxFunction>),
+    Native(NativeFunction),
+    List(Rc<RefCell<Vec<Type>>>),
+    Error(Box<ErrorValue>),
+    Module(Rc<Module>),
 }
 
 impl std::fmt::Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         fn type_helper(t: &Type) -> String {
             match t {
-                Type::Nil => "".to_string(),
+                Type::Nil => "nil".to_string(),
                 Type::Bool(v) => v.to_string(),
-                Type::Number(v) => v.to_string(),
+                Type::Number(v) => format_number(*v),
                 Type::String(v) => v.clone(),
                 Type::Any(v) => type_helper(v),
                 Type::Fun(v) => match &v.declaration {
-                    Stmt::Function { name, params, body } => {
-                        format!("<fn {}>", name.lexeme)
-                    }
-                    _ => panic!("todo"),
+                    Stmt::Function { name, .. } => format!("<fn {}>", name.lexeme),
+                    _ => unreachable!("LoxFunction always wraps Stmt::Function"),
                 },
+                Type::Native(v) => format!("<native fn {}>", v.name),
+                Type::List(v) => {
+                    let items = v.borrow().iter().map(type_helper).collect::<Vec<_>>();
+                    format!("[{}]", items.join(", "))
+                }
+                Type::Error(v) => format!("{}: {}", v.kind, v.message),
+                Type::Module(v) => format!("<module {}>", v.name),
             }
         }
 
@@ -34,6 +46,75 @@ impl std::fmt::Display for Type {
     }
 }
 
+impl Type {
+    // the value as the REPL and debugging tools show it: strings quoted and escaped so that
+    // `"1"` and `1` differ, functions with their arity. print and str() use Display.
+    pub fn repr(&self) -> String {
+        match self {
+            Type::Any(v) => v.repr(),
+            Type::String(v) => quote(v),
+            Type::Fun(v) => match &v.declaration {
+                Stmt::Function { name, params, .. } => {
+                    format!("<fn {}/{}>", name.lexeme, params.len())
+                }
+                _ => unreachable!("LoxFunction always wraps Stmt::Function"),
+            },
+            Type::Native(v) => format!("<native fn {}/{}>", v.name, v.arity),
+            Type::List(v) => {
+                let items = v.borrow().iter().map(Type::repr).collect::<Vec<_>>();
+                format!("[{}]", items.join(", "))
+            }
+            Type::Error(v) => format!("<error {} {}>", v.kind, quote(&v.message)),
+            Type::Nil | Type::Bool(_) | Type::Number(_) | Type::Module(_) => self.to_string(),
+        }
+    }
+}
+
+fn quote(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+
+    out
+}
+
+// canonical text of a number, shared by print and str().
+// integers have no decimal point, fractions use the shortest round-trip digits and
+// very large or very small magnitudes switch to exponent notation, e.g. 1e+21, 1e-7.
+pub fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        return "nan".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    if n == 0.0 {
+        return if n.is_sign_negative() { "-0" } else { "0" }.to_string();
+    }
+
+    let abs = n.abs();
+    if !(1e-6..1e21).contains(&abs) {
+        // rust gives "1e21" and "1.5e-8".
+        let text = format!("{:e}", n);
+        return match text.split_once('e') {
+            Some((mantissa, exp)) if !exp.starts_with('-') => format!("{}e+{}", mantissa, exp),
+            _ => text,
+        };
+    }
+
+    n.to_string()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TokenType {
     // Single-char tokens.
@@ -41,13 +122,22 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     COMMA,
     DOT,
     MINUS,
     PLUS,
     SEMICOLON,
+    QUESTION,
+    COLON,
     SLASH,
     STAR,
+    PERCENT,
+    AMPERSAND,
+    PIPE,
+    CARET,
+    TILDE,
     // one or two char tokens.
     BANG,
     BangEqual,
@@ -57,6 +147,19 @@ pub enum TokenType {
     GreaterEqual,
     LESS,
     LessEqual,
+    StarStar,
+    LessLess,
+    GreaterGreater,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    PlusPlus,
+    MinusMinus,
+    FatArrow,
+    DotDot,
+    DotDotEqual,
     // literals.
     IDENTIFIER,
     STRING,
@@ -79,6 +182,12 @@ pub enum TokenType {
     VAR,
     WHILE,
     BREAK,
+    MATCH,
+    TRY,
+    CATCH,
+    FINALLY,
+    THROW,
+    IMPORT,
 
     EOF,
 }
@@ -89,10 +198,12 @@ pub struct Token {
     pub lexeme: String,
     pub literal: Type, // TODO
     pub line: usize,
+    // 1-based, counted in chars; 0 for tokens made up by the parser.
+    pub column: usize,
 }
 
-impl ToString for Token {
-    fn to_string(&self) -> String {
-        format!("{:?} {} {:?}", self.token_type, self.lexeme, self.literal)
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {} {:?}", self.token_type, self.lexeme, self.literal)
     }
 }
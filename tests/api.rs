@@ -0,0 +1,259 @@
+use anyhow::Result;
+use myjlox::{Interpreter, MyError, Type};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_run_and_get_global() -> Result<()> {
+    let interpreter = Interpreter::new(false);
+    interpreter.run("var a = 1; var b = a + 2;")?;
+
+    assert_eq!(interpreter.get_global("b"), Some(Type::Number(3.0)));
+    assert_eq!(interpreter.get_global("missing"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_set_global_and_eval() -> Result<()> {
+    let interpreter = Interpreter::new(false);
+    interpreter.set_global("name", Type::String("lox".into()));
+
+    let result = interpreter.eval("\"hello \" + name")?;
+    assert_eq!(result, Type::String("hello lox".into()));
+
+    Ok(())
+}
+
+#[test]
+fn test_names() -> Result<()> {
+    let interpreter = Interpreter::new(false);
+    interpreter.run("var total = 1; fun twice(x) { return x * 2; }")?;
+
+    let names = interpreter.names();
+    assert!(names.iter().any(|name| name == "total"));
+    assert!(names.iter().any(|name| name == "twice"));
+    // builtins too, once each and in order.
+    assert!(names.iter().any(|name| name == "clock"));
+    assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+
+    Ok(())
+}
+
+#[test]
+fn test_register_native() -> Result<()> {
+    let interpreter = Interpreter::new(false);
+    interpreter.register_native("double", 1, |_, arguments| match arguments[0] {
+        Type::Number(n) => Ok(Type::Number(n * 2.0)),
+        _ => Err(MyError::CastError("Operand must be a number".into()).into()),
+    });
+
+    assert_eq!(interpreter.eval("double(21)")?, Type::Number(42.0));
+    interpreter.run("var x = double(2);")?;
+    assert_eq!(interpreter.get_global("x"), Some(Type::Number(4.0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_native_arity_mismatch() {
+    let interpreter = Interpreter::new(false);
+    interpreter.register_native("one", 1, |_, _| Ok(Type::Number(1.0)));
+
+    let err = interpreter.eval("one(1, 2)").unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<MyError>(),
+        Some(MyError::ArityMismatchError(1, 2))
+    ));
+}
+
+#[test]
+fn test_eval_rejects_trailing_tokens() {
+    let interpreter = Interpreter::new(false);
+    assert!(interpreter.eval("1 + 2 3").is_err());
+}
+
+#[test]
+fn test_import_cycle() {
+    let interpreter = Interpreter::new(false);
+    let err = interpreter
+        .run_file(Path::new("tests/input/modules/cycle_a.lox"))
+        .unwrap_err();
+
+    match err.downcast_ref::<MyError>() {
+        Some(MyError::ImportCycleError(chain)) => {
+            assert!(chain.contains("cycle_a.lox -> "));
+            assert!(chain.ends_with("cycle_a.lox"));
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+fn test_import_not_found() {
+    let interpreter = Interpreter::new(false);
+    let err = interpreter.run("import missing;").unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<MyError>(),
+        Some(MyError::ModuleNotFoundError(path)) if path == "missing.lox"
+    ));
+}
+
+#[test]
+fn test_module_members() -> Result<()> {
+    let interpreter = Interpreter::new(false);
+    interpreter.add_search_path(Path::new("tests/input/lib"));
+    interpreter.run("import greet;")?;
+
+    assert_eq!(
+        interpreter.eval("greet.greeting")?,
+        Type::String("hello, ".into())
+    );
+    assert!(interpreter.eval("greet.missing").is_err());
+
+    Ok(())
+}
+
+// a fresh directory under the system temp dir, unique per test.
+fn scratch_dir(name: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("myjlox-{}-{}", name, std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[test]
+fn test_fs_natives_need_permission() {
+    let interpreter = Interpreter::new(false);
+    let err = interpreter.run("readFile(\"Cargo.toml\");").unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<MyError>(),
+        Some(MyError::EnValueNotFoundError(_))
+    ));
+}
+
+#[test]
+fn test_fs_natives() -> Result<()> {
+    let dir = scratch_dir("fs")?;
+    let interpreter = Interpreter::new(false);
+    interpreter.allow_read(&[&dir])?;
+    interpreter.allow_write(&[&dir])?;
+    interpreter.set_global("dir", Type::String(dir.display().to_string()));
+
+    interpreter.run(
+        r#"
+        var path = dir + "/notes.txt";
+        writeFile(path, "one");
+        appendFile(path, ",two");
+        var contents = readFile(path);
+        var listed = listDir(dir);
+        var existed = exists(path);
+        remove(path);
+        var removed = !exists(path);
+        "#,
+    )?;
+
+    assert_eq!(
+        interpreter.get_global("contents"),
+        Some(Type::String("one,two".into()))
+    );
+    assert_eq!(
+        interpreter.eval("str(listed)")?,
+        Type::String("[notes.txt]".into())
+    );
+    assert_eq!(interpreter.get_global("existed"), Some(Type::Bool(true)));
+    assert_eq!(interpreter.get_global("removed"), Some(Type::Bool(true)));
+
+    Ok(())
+}
+
+#[test]
+fn test_fs_scope() -> Result<()> {
+    let dir = scratch_dir("scope")?;
+    let interpreter = Interpreter::new(false);
+    interpreter.allow_read(&[&dir])?;
+    interpreter.allow_write(&[&dir])?;
+    interpreter.set_global("dir", Type::String(dir.display().to_string()));
+
+    for source in [
+        "readFile(\"Cargo.toml\");",
+        "writeFile(dir + \"/../escape.txt\", \"x\");",
+        "remove(\"Cargo.toml\");",
+        // the scope itself is not inside it.
+        "remove(dir);",
+        "remove(dir + \"/.\");",
+    ] {
+        let err = interpreter.run(source).unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<MyError>(),
+                Some(MyError::PermissionError(_))
+            ),
+            "{source}: {err}"
+        );
+    }
+
+    assert!(dir.is_dir());
+
+    // failures inside the scope are catchable runtime errors.
+    interpreter
+        .run("var kind; try { readFile(dir + \"/missing.txt\"); } catch (e) { kind = e.kind; }")?;
+    assert_eq!(
+        interpreter.get_global("kind"),
+        Some(Type::String("IoError".into()))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_injected_input() -> Result<()> {
+    let interpreter = Interpreter::new(false);
+    interpreter.set_input(Box::new(std::io::Cursor::new(
+        "first\nsecond\r\nrest\nof it",
+    )));
+    interpreter
+        .run("var a = readLine(); var b = readLine(); var c = readAll(); var d = readLine();")?;
+
+    assert_eq!(
+        interpreter.get_global("a"),
+        Some(Type::String("first".into()))
+    );
+    assert_eq!(
+        interpreter.get_global("b"),
+        Some(Type::String("second".into()))
+    );
+    assert_eq!(
+        interpreter.get_global("c"),
+        Some(Type::String("rest\nof it".into()))
+    );
+    assert_eq!(interpreter.get_global("d"), Some(Type::Nil));
+    assert_eq!(interpreter.eval("readAll()")?, Type::String("".into()));
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_does_not_share_stdin() -> Result<()> {
+    let interpreter =
+        Interpreter::with_output(true, Box::new(std::io::sink()), Box::new(std::io::sink()));
+    for source in ["readLine()", "readAll()"] {
+        let error = interpreter.eval(source).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<MyError>(),
+            Some(MyError::IoError(_))
+        ));
+    }
+
+    // injected input is still read.
+    interpreter.set_input(Box::new(std::io::Cursor::new("typed\n")));
+    assert_eq!(
+        interpreter.eval("readLine()")?,
+        Type::String("typed".into())
+    );
+
+    Ok(())
+}
@@ -72,3 +72,492 @@ fn test_break_loop() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_match_stmt() -> Result<()> {
+    let input_file = "tests/input/match.txt";
+    let expected_file = "tests/expected/match.txt";
+
+    let args = &[input_file];
+    let expected = fs::read_to_string(expected_file)?;
+
+    Command::cargo_bin(PROGRAM)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_exceptions() -> Result<()> {
+    let input_file = "tests/input/exceptions.txt";
+    let expected_file = "tests/expected/exceptions.txt";
+
+    let args = &[input_file];
+    let expected = fs::read_to_string(expected_file)?;
+
+    Command::cargo_bin(PROGRAM)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_modules() -> Result<()> {
+    let input_file = "tests/input/modules.txt";
+    let expected_file = "tests/expected/modules.txt";
+
+    let args = &["-I", "tests/input/lib", input_file];
+    let expected = fs::read_to_string(expected_file)?;
+
+    Command::cargo_bin(PROGRAM)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_string_library() -> Result<()> {
+    let input_file = "tests/input/strings.txt";
+    let expected_file = "tests/expected/strings.txt";
+
+    let args = &[input_file];
+    let expected = fs::read_to_string(expected_file)?;
+
+    Command::cargo_bin(PROGRAM)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_fs_permission_flags() -> Result<()> {
+    let input_file = "tests/input/read_file.txt";
+    let expected = fs::read_to_string("tests/input/modules/cycle_b.lox")?;
+
+    Command::cargo_bin(PROGRAM)?
+        .args([input_file])
+        .assert()
+        .failure();
+    Command::cargo_bin(PROGRAM)?
+        .args(["--allow-read=tests/input/modules", input_file])
+        .assert()
+        .success()
+        .stdout(expected);
+    Command::cargo_bin(PROGRAM)?
+        .args(["--allow-read=src", input_file])
+        .assert()
+        .failure();
+    // a bare flag allows everything, whatever directories are also given.
+    Command::cargo_bin(PROGRAM)?
+        .args(["--allow-read", "--allow-read=src", input_file])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/input/modules/cycle_b.lox")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_read_stdin() -> Result<()> {
+    let input_file = "tests/input/read_stdin.txt";
+    let expected_file = "tests/expected/read_stdin.txt";
+
+    let args = &[input_file];
+    let expected = fs::read_to_string(expected_file)?;
+
+    Command::cargo_bin(PROGRAM)?
+        .args(args)
+        .write_stdin("1\n2.5\nabc\n -4 \r\n10")
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_script_args_env_and_exit() -> Result<()> {
+    let input_file = "tests/input/script_args.txt";
+    let expected_file = "tests/expected/script_args.txt";
+
+    let args = &[input_file, "one", "-v", "--two"];
+    let expected = fs::read_to_string(expected_file)?;
+
+    Command::cargo_bin(PROGRAM)?
+        .args(args)
+        .env("MYJLOX_GREETING", "hi")
+        .env_remove("MYJLOX_SURELY_UNSET")
+        .assert()
+        .code(3)
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_exit_code_syntax_error() -> Result<()> {
+    Command::cargo_bin(PROGRAM)?
+        .args(["tests/input/syntax_error.txt"])
+        .assert()
+        .code(65)
+        .stdout("")
+        .stderr("Parsing error occurred \"[line 2] Error at ';': Expected expression.\"\n");
+    Command::cargo_bin(PROGRAM)?
+        .args(["tests/input/scan_error.txt"])
+        .assert()
+        .code(65);
+
+    Ok(())
+}
+
+#[test]
+fn test_exit_code_runtime_error() -> Result<()> {
+    Command::cargo_bin(PROGRAM)?
+        .args(["tests/input/runtime_error.txt"])
+        .assert()
+        .code(70)
+        .stdout("before\n")
+        .stderr("Divided by zero Error occurred\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_exit_code_io_error() -> Result<()> {
+    Command::cargo_bin(PROGRAM)?
+        .args(["tests/input/does_not_exist.txt"])
+        .assert()
+        .code(74);
+
+    Ok(())
+}
+
+#[test]
+fn test_program_from_stdin() -> Result<()> {
+    // piped stdin runs as one program, without prompts.
+    Command::cargo_bin(PROGRAM)?
+        .write_stdin("print 1;\nexit(4);\nprint 2;\n")
+        .assert()
+        .code(4)
+        .stdout("1\n");
+    Command::cargo_bin(PROGRAM)?
+        .args(["-", "x"])
+        .write_stdin("print args;\nprint nope;\n")
+        .assert()
+        .code(70)
+        .stdout("[x]\n")
+        .stderr("Undefined variable nope.\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_eval_option() -> Result<()> {
+    Command::cargo_bin(PROGRAM)?
+        .args(["-e", "print 1 + 2; print args;", "a", "-b"])
+        .assert()
+        .success()
+        .stdout("3\n[a, -b]\n");
+    Command::cargo_bin(PROGRAM)?
+        .args(["--eval", "print 1 +;"])
+        .assert()
+        .code(65)
+        .stdout("");
+
+    Ok(())
+}
+
+#[test]
+fn test_tokens_and_ast_dumps() -> Result<()> {
+    let input_file = "tests/input/dump.txt";
+
+    Command::cargo_bin(PROGRAM)?
+        .args(["tokens", input_file])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/expected/dump_tokens.txt")?);
+    Command::cargo_bin(PROGRAM)?
+        .args(["ast", input_file])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/expected/dump_ast.txt")?);
+
+    let output = Command::cargo_bin(PROGRAM)?
+        .args(["ast", "--format", "json", "-e", "print 1 + x;"])
+        .output()?;
+    let ast: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(ast[0]["type"], "Print");
+    assert_eq!(ast[0]["expression"]["op"], "+");
+    assert_eq!(ast[0]["expression"]["right"]["name"], "x");
+
+    let output = Command::cargo_bin(PROGRAM)?
+        .args(["tokens", "--format=json", "-e", "a\n  \"é\";"])
+        .output()?;
+    let tokens: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(tokens[1]["literal"], "é");
+    assert_eq!(
+        (&tokens[1]["line"], &tokens[1]["column"]),
+        (&2.into(), &3.into())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_check_subcommand() -> Result<()> {
+    Command::cargo_bin(PROGRAM)?
+        .args(["check", "tests/input/dump.txt"])
+        .assert()
+        .success()
+        .stdout("");
+    // nothing runs, and every problem is listed.
+    Command::cargo_bin(PROGRAM)?
+        .args(["check", "-e", "print 1; { var a = a; } break;"])
+        .assert()
+        .code(65)
+        .stdout("")
+        .stderr(concat!(
+            "Static error occurred \"[line 1] Error at 'a': Can't read local variable in its own initializer.\"\n",
+            "Static error occurred \"[line 1] Error at 'break': Can't use 'break' outside of a loop.\"\n",
+        ));
+
+    // nothing to check is a usage error, not a wait for stdin.
+    let output = Command::cargo_bin(PROGRAM)?.arg("check").assert().code(2);
+    assert!(String::from_utf8_lossy(&output.get_output().stderr).contains("required"));
+    // options for running scripts don't apply to the commands that only read them.
+    for command in ["check", "tokens", "ast"] {
+        let output = Command::cargo_bin(PROGRAM)?
+            .args([command, "-I", "tests/input/lib", "tests/input/dump.txt"])
+            .assert()
+            .code(2);
+        assert!(String::from_utf8_lossy(&output.get_output().stderr)
+            .contains("unexpected argument '-I'"));
+    }
+    Command::cargo_bin(PROGRAM)?
+        .args(["check", "--allow-read", "tests/input/dump.txt"])
+        .assert()
+        .code(2);
+
+    Ok(())
+}
+
+#[test]
+fn test_run_subcommand() -> Result<()> {
+    Command::cargo_bin(PROGRAM)?
+        .args(["run", "-I", "tests/input/lib", "tests/input/modules.txt"])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/expected/modules.txt")?);
+    Command::cargo_bin(PROGRAM)?
+        .args(["run", "-e", "print args;", "tokens"])
+        .assert()
+        .success()
+        .stdout("[tokens]\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_error_format_json() -> Result<()> {
+    let diagnostics = |args: &[&str], code: i32| -> Result<Vec<serde_json::Value>> {
+        let output = Command::cargo_bin(PROGRAM)?
+            .args(args)
+            .assert()
+            .code(code)
+            .get_output()
+            .stderr
+            .clone();
+        Ok(String::from_utf8(output)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<_>>()?)
+    };
+
+    let parse = &diagnostics(&["--error-format=json", "tests/input/syntax_error.txt"], 65)?[0];
+    assert_eq!(parse["severity"], "error");
+    assert_eq!(parse["code"], "ParseError");
+    assert_eq!(parse["message"], "Expected expression.");
+    assert!(parse["file"]
+        .as_str()
+        .is_some_and(|file| file.ends_with("syntax_error.txt")));
+    assert_eq!((&parse["line"], &parse["column"]), (&2.into(), &10.into()));
+    assert_eq!(parse["end_column"], 11);
+
+    let scan = &diagnostics(&["--error-format=json", "tests/input/scan_error.txt"], 65)?[0];
+    assert_eq!(scan["code"], "ScanError");
+    assert_eq!(scan["message"], "Unterminated string.");
+    assert_eq!((&scan["line"], &scan["column"]), (&1.into(), &9.into()));
+
+    let runtime = &diagnostics(
+        &["--error-format=json", "tests/input/runtime_error.txt"],
+        70,
+    )?[0];
+    assert_eq!(runtime["code"], "DividedbyzeroError");
+    assert_eq!(
+        (&runtime["line"], &runtime["column"]),
+        (&2.into(), &9.into())
+    );
+    assert_eq!(runtime["notes"], serde_json::json!([]));
+    // the bare message; the line is in the span.
+    assert_eq!(runtime["message"], "Divided by zero.");
+    let cast = &diagnostics(&["--error-format=json", "-e", "\n-\"a\";"], 70)?[0];
+    assert_eq!(cast["message"], "Operand must be a number");
+    assert_eq!(cast["line"], 2);
+
+    // one object per problem, without a file for -e.
+    let checked = diagnostics(
+        &[
+            "check",
+            "--error-format=json",
+            "-e",
+            "{ var a = a; } break;",
+        ],
+        65,
+    )?;
+    assert_eq!(checked.len(), 2);
+    assert_eq!(checked[1]["code"], "StaticError");
+    assert_eq!(checked[1]["file"], serde_json::Value::Null);
+    assert_eq!(checked[1]["column"], 16);
+
+    Ok(())
+}
+
+#[test]
+fn test_error_format_before_subcommand() -> Result<()> {
+    let input_file = "tests/input/syntax_error.txt";
+    let runs: &[&[&str]] = &[
+        &["--error-format=json", "run", input_file],
+        &["--error-format", "json", "check", input_file],
+        &["--error-format=json", "tokens", "-e", "\"open"],
+        &["--error-format=json", "ast", input_file],
+    ];
+    for args in runs {
+        let output = Command::cargo_bin(PROGRAM)?
+            .args(*args)
+            .assert()
+            .code(65)
+            .get_output()
+            .stderr
+            .clone();
+        let diagnostic: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_ne!(diagnostic["code"], "IoError", "{:?}", args);
+    }
+    let output = Command::cargo_bin(PROGRAM)?
+        .args(["--error-format=json", "repl"])
+        .write_stdin(":what\n")
+        .assert()
+        .success()
+        .get_output()
+        .stderr
+        .clone();
+    let diagnostic: serde_json::Value = serde_json::from_slice(&output)?;
+    assert_eq!(diagnostic["code"], "UnknownCommand");
+
+    Ok(())
+}
+
+#[test]
+fn test_exit_code_repl() -> Result<()> {
+    // errors are reported and the session goes on, ending with the last failure's status.
+    Command::cargo_bin(PROGRAM)?
+        .arg("repl")
+        .write_stdin("x = ;\nprint 1;\n")
+        .assert()
+        .code(65)
+        .stdout("> > 1\n> ")
+        .stderr("Parsing error occurred \"[line 1] Error at ';': Expected expression.\"\n");
+    Command::cargo_bin(PROGRAM)?
+        .arg("repl")
+        .write_stdin("x = ;\nprint nope;\nprint 1;\n")
+        .assert()
+        .code(70);
+    Command::cargo_bin(PROGRAM)?
+        .arg("repl")
+        .write_stdin("print nope;\nexit(4);\nprint 2;\n")
+        .assert()
+        .code(4);
+    Command::cargo_bin(PROGRAM)?
+        .arg("repl")
+        .write_stdin("print 1;\n")
+        .assert()
+        .success();
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_multiline_input() -> Result<()> {
+    Command::cargo_bin(PROGRAM)?
+        .arg("repl")
+        .write_stdin(
+            "fun add(a,\n  b) {\n  return a + b;\n}\nprint add(1, 2);\nprint (1 +\n\nprint 5;\n",
+        )
+        .assert()
+        .code(65)
+        .stdout("> . . . > 3\n> . > 5\n> ")
+        .stderr("Parsing error occurred \"[line 3] Error at end: Expected expression.\"\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_repl_commands() -> Result<()> {
+    let script = std::env::temp_dir().join(format!("myjlox-session-{}.lox", std::process::id()));
+    let session = format!(
+        "var a = 1;\nvar b;\nprint nope;\n:env\n:ast 1 + 2 * a\n:tokens a;\n:save {0}\n:reset\n:env\n:load {0}\nprint a;\n:what\n:save {0}\n",
+        script.display()
+    );
+    Command::cargo_bin(PROGRAM)?
+        .arg("repl")
+        .write_stdin(session)
+        .assert()
+        .code(70)
+        .stdout(concat!(
+            "> > > > a = 1\nb (uninitialized)\n",
+            "> (expression (+ 1 (* 2 a)))\n",
+            "> 1:1 IDENTIFIER a\n1:2 SEMICOLON ;\n1:3 EOF \n",
+            "> > > > > 1\n> > > ",
+        ))
+        .stderr("Undefined variable nope.\nUnknown command :what, see :help.\n");
+    // only what ran without error is saved, a loaded file as its source.
+    assert_eq!(
+        fs::read_to_string(&script)?,
+        "var a = 1;\nvar b;\nprint a;\n"
+    );
+    fs::remove_file(script)?;
+
+    // messages about commands are diagnostics too.
+    let output = Command::cargo_bin(PROGRAM)?
+        .args(["repl", "--error-format=json"])
+        .write_stdin(":what\n:load\n")
+        .assert()
+        .success()
+        .get_output()
+        .stderr
+        .clone();
+    let warnings = String::from_utf8(output)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<serde_json::Result<Vec<serde_json::Value>>>()?;
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0]["severity"], "warning");
+    assert_eq!(warnings[0]["code"], "UnknownCommand");
+    assert_eq!(
+        warnings[1]["message"],
+        ":load needs an argument, see :help."
+    );
+
+    Ok(())
+}